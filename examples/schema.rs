@@ -4,7 +4,9 @@ use std::fs::create_dir_all;
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
 use collectxyz_guestbook_tutorial_contract::msg::{
-    EntriesResponse, ExecuteMsg, InstantiateMsg, QueryMsg,
+    CanPostResponse, EntriesResponse, EntryFeeQuoteResponse, ExecuteMsg, InstantiateMsg,
+    LocationResponse, MigrateMsg, ModeratorsResponse, OwnerResponse, PendingDistributionResponse,
+    QueryMsg, ReceiveMsg, ReportsResponse, SimulateCreateEntryResponse,
 };
 
 fn main() {
@@ -15,6 +17,16 @@ fn main() {
 
     export_schema(&schema_for!(InstantiateMsg), &out_dir);
     export_schema(&schema_for!(ExecuteMsg), &out_dir);
+    export_schema(&schema_for!(ReceiveMsg), &out_dir);
     export_schema(&schema_for!(QueryMsg), &out_dir);
+    export_schema(&schema_for!(MigrateMsg), &out_dir);
     export_schema(&schema_for!(EntriesResponse), &out_dir);
+    export_schema(&schema_for!(OwnerResponse), &out_dir);
+    export_schema(&schema_for!(EntryFeeQuoteResponse), &out_dir);
+    export_schema(&schema_for!(LocationResponse), &out_dir);
+    export_schema(&schema_for!(CanPostResponse), &out_dir);
+    export_schema(&schema_for!(SimulateCreateEntryResponse), &out_dir);
+    export_schema(&schema_for!(ReportsResponse), &out_dir);
+    export_schema(&schema_for!(ModeratorsResponse), &out_dir);
+    export_schema(&schema_for!(PendingDistributionResponse), &out_dir);
 }
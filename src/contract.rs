@@ -1,129 +1,2035 @@
-use collectxyz::nft::{Coordinates, QueryMsg as XyzQueryMsg, XyzTokenInfo};
+use std::convert::TryFrom;
+
+use collectxyz::nft::{Config as XyzConfig, Coordinates, QueryMsg as XyzQueryMsg, XyzTokenInfo};
 use cosmwasm_std::{
-    entry_point, to_binary, BankMsg, Binary, Coin, Deps, DepsMut, Env, MessageInfo, Order,
-    Response, StdResult,
+    entry_point, from_binary, to_binary, Addr, BankMsg, Binary, Coin, Deps, DepsMut, Empty, Env,
+    MessageInfo, Order, Pair, Response, StdError, StdResult, Storage, Timestamp, Uint128,
+};
+use cw2::{get_contract_version, set_contract_version};
+use cw20::{
+    BalanceResponse, Cw20CoinVerified, Cw20Contract, Cw20ExecuteMsg, Cw20QueryMsg, Cw20ReceiveMsg,
 };
-use cw2::set_contract_version;
-use cw_storage_plus::Bound;
+use cw721::ApprovedForAllResponse;
+use cw_storage_plus::{Bound, PrimaryKey, U32Key, U64Key};
 
 use crate::error::ContractError;
-use crate::msg::{EntriesResponse, ExecuteMsg, InstantiateMsg, QueryMsg};
-use crate::state::{entries, entries_key, Config, Entry, CONFIG, OWNER};
+use crate::migrations::{parse_version, run_migrations};
+use crate::msg::{
+    CanPostResponse, EntriesResponse, EntryFeeQuoteResponse, ExecuteMsg, InstantiateMsg,
+    LocationResponse, MigrateMsg, ModeratorsResponse, OrderBy, OwnerResponse, PayeeMsg,
+    PendingDistributionResponse, PendingPayment, QueryMsg, ReceiveMsg, ReportsResponse,
+    SimulateCreateEntryResponse, UpdateConfigMsg, Violation,
+};
+use crate::state::{
+    coordinates_to_point, entries, entries_key, location_score_rank, pin_rank, region_bucket,
+    region_bucket_to_bytes, reports, Config, ContentPolicy, Entry, EntryKey, EntryRef, Location,
+    Payee, Point, PostingHistory, ReactionCount, RegionBucket, Report, CONFIG, CONTENT_POLICY,
+    ENTRY_SEQ, LOCATIONS, LOCATION_ENTRY_COUNTS, MODERATORS, OWNER, PAYEES, PENDING_OWNER,
+    POSTING_HISTORY, REACTIONS, REPORT_SEQ,
+};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:entries-tutorial-contract";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
-#[entry_point]
-pub fn instantiate(
+#[entry_point]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    validate_config(deps.as_ref(), &msg.config)?;
+    CONFIG.save(deps.storage, &msg.config)?;
+    OWNER.save(deps.storage, &info.sender.to_string())?;
+
+    Ok(Response::default()
+        .add_attribute("method", "instantiate")
+        .add_attribute("owner", info.sender))
+}
+
+#[entry_point]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::CreateEntry {
+            author_xyz_id,
+            text,
+        } => execute_create_entry(deps, env, info, author_xyz_id, text),
+        ExecuteMsg::CreateLateEntry {
+            author_xyz_id,
+            text,
+        } => execute_create_late_entry(deps, env, info, author_xyz_id, text),
+        ExecuteMsg::ReplyToEntry {
+            parent_author_xyz_id,
+            parent_coordinates,
+            parent_index,
+            author_xyz_id,
+            text,
+        } => {
+            let parent = entry_ref(parent_author_xyz_id, parent_coordinates, parent_index);
+            execute_reply_to_entry(deps, env, info, parent, author_xyz_id, text)
+        }
+        ExecuteMsg::UpdateEntry {
+            author_xyz_id,
+            coordinates,
+            index,
+            text,
+        } => {
+            let entry_ref = entry_ref(author_xyz_id, coordinates, index);
+            execute_update_entry(deps, env, info, entry_ref, text)
+        }
+        ExecuteMsg::DeleteEntry {
+            author_xyz_id,
+            coordinates,
+            index,
+        } => {
+            let entry_ref = entry_ref(author_xyz_id, coordinates, index);
+            execute_delete_entry(deps, env, info, entry_ref)
+        }
+        ExecuteMsg::TipEntry {
+            author_xyz_id,
+            coordinates,
+            index,
+        } => {
+            let entry_ref = entry_ref(author_xyz_id, coordinates, index);
+            execute_tip_entry(deps, env, info, entry_ref)
+        }
+        ExecuteMsg::ReactToEntry {
+            author_xyz_id,
+            coordinates,
+            index,
+            reaction,
+            reactor_xyz_id,
+        } => {
+            let entry_ref = entry_ref(author_xyz_id, coordinates, index);
+            execute_react_to_entry(deps, env, info, entry_ref, reaction, reactor_xyz_id)
+        }
+        ExecuteMsg::ClaimLocation { xyz_id } => execute_claim_location(deps, env, info, xyz_id),
+        ExecuteMsg::SetLocationSurcharge {
+            coordinates,
+            surcharge_bps,
+        } => execute_set_location_surcharge(deps, env, info, coordinates, surcharge_bps),
+        ExecuteMsg::SetEntryPinned {
+            author_xyz_id,
+            coordinates,
+            index,
+            pinned,
+        } => {
+            let entry_ref = entry_ref(author_xyz_id, coordinates, index);
+            execute_set_entry_pinned(deps, env, info, entry_ref, pinned)
+        }
+        ExecuteMsg::SetEntryHiddenByHost {
+            author_xyz_id,
+            coordinates,
+            index,
+            hidden,
+        } => {
+            let entry_ref = entry_ref(author_xyz_id, coordinates, index);
+            execute_set_entry_hidden_by_host(deps, env, info, entry_ref, hidden)
+        }
+        ExecuteMsg::UpdateModerators { add, remove } => {
+            execute_update_moderators(deps, env, info, add, remove)
+        }
+        ExecuteMsg::ReportEntry {
+            author_xyz_id,
+            coordinates,
+            index,
+            reporter_xyz_id,
+            reason,
+        } => {
+            let entry_ref = entry_ref(author_xyz_id, coordinates, index);
+            execute_report_entry(deps, env, info, entry_ref, reporter_xyz_id, reason)
+        }
+        ExecuteMsg::HideEntry {
+            author_xyz_id,
+            coordinates,
+            index,
+        } => {
+            let entry_ref = entry_ref(author_xyz_id, coordinates, index);
+            execute_set_entry_hidden(deps, env, info, entry_ref, true)
+        }
+        ExecuteMsg::RestoreEntry {
+            author_xyz_id,
+            coordinates,
+            index,
+        } => {
+            let entry_ref = entry_ref(author_xyz_id, coordinates, index);
+            execute_set_entry_hidden(deps, env, info, entry_ref, false)
+        }
+        ExecuteMsg::Receive(msg) => execute_receive(deps, env, info, msg),
+        ExecuteMsg::Withdraw { amount } => execute_withdraw(deps, env, info, amount),
+        ExecuteMsg::WithdrawCw20 {
+            token_contract,
+            amount,
+        } => execute_withdraw_cw20(deps, env, info, token_contract, amount),
+        ExecuteMsg::UpdateConfig(msg) => execute_update_config(deps, env, info, msg),
+        ExecuteMsg::UpdateContentPolicy(policy) => {
+            execute_update_content_policy(deps, env, info, policy)
+        }
+        ExecuteMsg::UpdatePayees { payees } => execute_update_payees(deps, env, info, payees),
+        ExecuteMsg::Distribute {} => execute_distribute(deps, env, info),
+        ExecuteMsg::ProposeNewOwner { new_owner } => {
+            execute_propose_new_owner(deps, env, info, new_owner)
+        }
+        ExecuteMsg::AcceptOwnership {} => execute_accept_ownership(deps, env, info),
+        ExecuteMsg::CancelOwnershipProposal {} => {
+            execute_cancel_ownership_proposal(deps, env, info)
+        }
+        ExecuteMsg::RenounceOwnership {} => execute_renounce_ownership(deps, env, info),
+    }
+}
+
+// Refer to an entry by the fields identifying it in a message, where an unset index refers to the
+// author xyz's first entry at the location.
+fn entry_ref(author_xyz_id: String, coordinates: Coordinates, index: Option<u32>) -> EntryRef {
+    EntryRef {
+        author_xyz_id,
+        coordinates,
+        index: index.unwrap_or_default(),
+    }
+}
+
+// Check that the message sender is the contract owner, returning the owner's address.
+// Once ownership has been renounced, nobody passes this check.
+fn assert_owner(deps: Deps, info: &MessageInfo) -> Result<String, ContractError> {
+    match OWNER.may_load(deps.storage)? {
+        Some(owner) if info.sender == owner => Ok(owner),
+        _ => Err(ContractError::Unauthorized {}),
+    }
+}
+
+// Look up an xyz on the xyz NFT contract.
+fn query_xyz(deps: Deps, config: &Config, xyz_id: &str) -> StdResult<XyzTokenInfo> {
+    deps.querier.query_wasm_smart(
+        config.xyz_nft_contract.clone(),
+        &XyzQueryMsg::XyzNftInfo {
+            token_id: xyz_id.to_string(),
+        },
+    )
+}
+
+// Check that the given sender currently owns the given xyz.
+fn assert_xyz_owner(
+    deps: Deps,
+    config: &Config,
+    sender: &Addr,
+    xyz_id: &str,
+) -> Result<XyzTokenInfo, ContractError> {
+    let xyz = query_xyz(deps, config, xyz_id)?;
+    if xyz.owner != *sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(xyz)
+}
+
+// The most operators that cw721 contracts return in a single page.
+const OPERATOR_PAGE_LIMIT: u32 = 30;
+
+// Check whether an xyz owner has approved the given address as an operator for all of their
+// xyzs, paging through the owner's unexpired operators on the xyz NFT contract.
+fn is_xyz_operator(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+    owner: &Addr,
+    operator: &Addr,
+) -> StdResult<bool> {
+    let mut start_after = None;
+    loop {
+        let res: ApprovedForAllResponse = deps.querier.query_wasm_smart(
+            config.xyz_nft_contract.clone(),
+            &XyzQueryMsg::ApprovedForAll {
+                owner: owner.to_string(),
+                include_expired: Some(false),
+                start_after,
+                limit: Some(OPERATOR_PAGE_LIMIT),
+            },
+        )?;
+        if res.operators.iter().any(|approval| {
+            approval.spender == *operator && !approval.expires.is_expired(&env.block)
+        }) {
+            return Ok(true);
+        }
+        if res.operators.len() < OPERATOR_PAGE_LIMIT as usize {
+            return Ok(false);
+        }
+        start_after = res
+            .operators
+            .last()
+            .map(|approval| approval.spender.clone());
+    }
+}
+
+// Check whether the given sender may post for the given xyz: its current owner, an address with
+// an unexpired approval for the xyz, or an operator the owner has approved for all of their xyzs.
+fn is_xyz_poster(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+    xyz: &XyzTokenInfo,
+    sender: &Addr,
+) -> StdResult<bool> {
+    Ok(xyz.owner == *sender
+        || xyz.approvals.iter().any(|approval| {
+            approval.spender == *sender && !approval.expires.is_expired(&env.block)
+        })
+        || is_xyz_operator(deps, env, config, &xyz.owner, sender)?)
+}
+
+// Find the xyz's previous location if it can still leave a late signature there: late signatures
+// are enabled, the xyz is still relocating, and the grace window since it started relocating
+// hasn't closed yet.
+//
+// The xyz NFT contract doesn't record when a relocation began, so it's worked out from the xyz's
+// arrival and the time the move takes under the NFT contract's current move settings. If those
+// settings have changed since the xyz moved, the window shifts by the difference.
+fn late_signature_coordinates(
+    deps: Deps,
+    config: &Config,
+    env: &Env,
+    xyz: &XyzTokenInfo,
+) -> StdResult<Option<Coordinates>> {
+    let prev_coordinates = match xyz.extension.prev_coordinates {
+        Some(prev_coordinates) if config.late_signature_grace_seconds > 0 => prev_coordinates,
+        _ => return Ok(None),
+    };
+    if xyz.extension.has_arrived(env.block.time) {
+        return Ok(None);
+    }
+
+    let xyz_config: XyzConfig = deps
+        .querier
+        .query_wasm_smart(config.xyz_nft_contract.clone(), &XyzQueryMsg::Config {})?;
+    let move_nanos = xyz_config
+        .move_nanos_per_step
+        .checked_mul(prev_coordinates.distance(xyz.extension.coordinates))
+        .and_then(|nanos| nanos.checked_add(xyz_config.base_move_nanos))
+        .unwrap_or(u64::MAX);
+    let departure = Timestamp::from_nanos(xyz.extension.arrival.nanos().saturating_sub(move_nanos));
+    let window_end = departure.plus_seconds(config.late_signature_grace_seconds);
+    if env.block.time >= window_end {
+        return Ok(None);
+    }
+    Ok(Some(prev_coordinates))
+}
+
+// Find when an xyz will have stayed at its current location for the configured minimum stay.
+fn stay_complete_at(config: &Config, xyz: &XyzTokenInfo) -> Timestamp {
+    xyz.extension.arrival.plus_seconds(config.min_stay_seconds)
+}
+
+// Load an existing entry, returning NotFound if there's no entry with the given key.
+fn load_entry(storage: &dyn Storage, key: EntryKey) -> Result<Entry, ContractError> {
+    entries()
+        .may_load(storage, key)?
+        .ok_or(ContractError::NotFound {})
+}
+
+// Check that entry text doesn't exceed the character limit, measured per the limit mode.
+fn assert_within_character_limit(config: &Config, text: &str) -> Result<(), ContractError> {
+    let length = config.limit_mode.length(text);
+    if length > config.character_limit as usize {
+        return Err(ContractError::CharacterLimitExceeded {
+            length: u32::try_from(length).unwrap_or(u32::MAX),
+            limit: config.character_limit,
+        });
+    }
+    Ok(())
+}
+
+// Check that text follows the content policy, if one has been set.
+fn assert_content_allowed(storage: &dyn Storage, text: &str) -> Result<(), ContractError> {
+    let policy = CONTENT_POLICY.may_load(storage)?.unwrap_or_default();
+    match policy.violation(text) {
+        Some(rule) => Err(ContractError::ContentPolicyViolation { rule }),
+        None => Ok(()),
+    }
+}
+
+// Check that the sent funds cover one of the accepted entry fees, returning the fee paid and
+// whatever is left over after the fee is deducted so that it can be refunded to the sender.
+fn deduct_entry_fee(
+    entry_fees: &[Coin],
+    funds: &[Coin],
+) -> Result<(Option<Coin>, Vec<Coin>), ContractError> {
+    if entry_fees.is_empty() {
+        return Ok((None, funds.to_vec()));
+    }
+
+    let fee = entry_fees.iter().find(|fee| {
+        // the fee is free, or a coin of the same denomination covers the amount
+        fee.amount.is_zero()
+            || funds
+                .iter()
+                .any(|coin| coin.denom == fee.denom && coin.amount >= fee.amount)
+    });
+    let fee = match fee {
+        Some(fee) => fee,
+        None => {
+            // Distinguish paying too little in an accepted denomination from not paying in one
+            let accepted_denom_sent = funds
+                .iter()
+                .any(|coin| entry_fees.iter().any(|fee| fee.denom == coin.denom));
+            if funds.is_empty() || accepted_denom_sent {
+                return Err(ContractError::InsufficientFunds {});
+            }
+            return Err(ContractError::WrongDenom {});
+        }
+    };
+
+    let refund = funds
+        .iter()
+        .map(|coin| {
+            let mut coin = coin.clone();
+            if coin.denom == fee.denom {
+                coin.amount = coin.amount.saturating_sub(fee.amount);
+            }
+            coin
+        })
+        .filter(|coin| !coin.amount.is_zero())
+        .collect();
+    Ok((Some(fee.clone()), refund))
+}
+
+// Check that the tokens sent by a CW20 contract cover its entry fee, returning the amount left
+// over after the fee is deducted so that it can be refunded to the sender.
+fn deduct_cw20_entry_fee(
+    cw20_entry_fees: &[Cw20CoinVerified],
+    token_contract: &Addr,
+    amount: Uint128,
+) -> Result<Uint128, ContractError> {
+    let fee = cw20_entry_fees
+        .iter()
+        .find(|fee| fee.address == *token_contract)
+        .ok_or(ContractError::WrongDenom {})?;
+    if amount < fee.amount {
+        return Err(ContractError::InsufficientFunds {});
+    }
+    Ok(amount - fee.amount)
+}
+
+pub fn execute_create_entry(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    author_xyz_id: String,
+    text: String,
+) -> Result<Response, ContractError> {
+    let entry = NewEntry {
+        author_xyz_id,
+        text,
+        parent: None,
+        late: false,
+    };
+    create_entry(deps, env, info.sender, entry, Payment::Native(info.funds))
+}
+
+pub fn execute_create_late_entry(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    author_xyz_id: String,
+    text: String,
+) -> Result<Response, ContractError> {
+    let entry = NewEntry {
+        author_xyz_id,
+        text,
+        parent: None,
+        late: true,
+    };
+    create_entry(deps, env, info.sender, entry, Payment::Native(info.funds))
+}
+
+pub fn execute_reply_to_entry(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    parent: EntryRef,
+    author_xyz_id: String,
+    text: String,
+) -> Result<Response, ContractError> {
+    let entry = NewEntry {
+        author_xyz_id,
+        text,
+        parent: Some(parent),
+        late: false,
+    };
+    create_entry(deps, env, info.sender, entry, Payment::Native(info.funds))
+}
+
+pub fn execute_receive(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    wrapper: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    // The message sender is the token contract, and the wrapped sender is the token holder
+    let sender = deps.api.addr_validate(&wrapper.sender)?;
+    let payment = Payment::Cw20 {
+        token_contract: info.sender,
+        amount: wrapper.amount,
+    };
+
+    let msg: ReceiveMsg = from_binary(&wrapper.msg)?;
+    let entry = match msg {
+        ReceiveMsg::CreateEntry {
+            author_xyz_id,
+            text,
+        } => NewEntry {
+            author_xyz_id,
+            text,
+            parent: None,
+            late: false,
+        },
+        ReceiveMsg::CreateLateEntry {
+            author_xyz_id,
+            text,
+        } => NewEntry {
+            author_xyz_id,
+            text,
+            parent: None,
+            late: true,
+        },
+        ReceiveMsg::ReplyToEntry {
+            parent_author_xyz_id,
+            parent_coordinates,
+            parent_index,
+            author_xyz_id,
+            text,
+        } => NewEntry {
+            author_xyz_id,
+            text,
+            parent: Some(entry_ref(
+                parent_author_xyz_id,
+                parent_coordinates,
+                parent_index,
+            )),
+            late: false,
+        },
+    };
+    create_entry(deps, env, sender, entry, payment)
+}
+
+// An entry to create for an xyz, either at its current location or, as a late signature, at its
+// previous location.
+struct NewEntry {
+    author_xyz_id: String,
+    text: String,
+    parent: Option<EntryRef>,
+    late: bool,
+}
+
+// The funds sent to pay for an entry.
+enum Payment {
+    Native(Vec<Coin>),
+    Cw20 {
+        token_contract: Addr,
+        amount: Uint128,
+    },
+}
+
+// The entry fee deducted from a payment, along with whatever is left over to refund.
+enum FeeDeduction {
+    Native {
+        fee: Option<Coin>,
+        refund: Vec<Coin>,
+    },
+    Cw20 {
+        token_contract: Addr,
+        paid: Uint128,
+        refund: Uint128,
+    },
+}
+
+// A new entry that has passed every check for creating it.
+struct ValidEntry {
+    config: Config,
+    coordinates: Coordinates,
+    index: u32,
+    fee: FeeDeduction,
+}
+
+// Run every check for creating an entry, collecting each rule the entry would violate rather than
+// stopping at the first one, so that the same checks can back both entry creation and its
+// simulation. If the author xyz can't be looked up, the checks that depend on it are skipped, and
+// if the entry's location can't be found, the payment is checked against the configured entry
+// fees, which are the least that any location charges.
+fn check_new_entry(
+    deps: Deps,
+    env: &Env,
+    sender: &Addr,
+    entry: &NewEntry,
+    payment: Payment,
+) -> StdResult<Result<ValidEntry, Vec<ContractError>>> {
+    let author_xyz_id = entry.author_xyz_id.as_str();
+    let text = entry.text.as_str();
+    let config = CONFIG.load(deps.storage)?;
+    let mut violations = vec![];
+
+    // Check that text doesn't exceed the character limit and follows the content policy
+    if let Err(err) = assert_within_character_limit(&config, text) {
+        violations.push(err);
+    }
+    if let Err(err) = assert_content_allowed(deps.storage, text) {
+        violations.push(err);
+    }
+
+    // Check that the entry being replied to exists
+    if let Some(parent) = &entry.parent {
+        if entries().may_load(deps.storage, parent.key())?.is_none() {
+            violations.push(ContractError::NotFound {});
+        }
+    }
+
+    // Look up the author xyz and check that the sender owns it or is approved to post for it
+    let xyz = match query_xyz(deps, &config, author_xyz_id) {
+        Ok(xyz) => Some(xyz),
+        Err(err) => {
+            violations.push(err.into());
+            None
+        }
+    };
+    if let Some(xyz) = &xyz {
+        if !is_xyz_poster(deps, env, &config, xyz, sender)? {
+            violations.push(ContractError::Unauthorized {});
+        }
+    }
+
+    // A late signature goes in the guestbook at the xyz's previous location, as long as the
+    // late signature window is open. Otherwise, check that the xyz isn't currently relocating,
+    // and that it has stayed at its location for the minimum stay since arriving.
+    let coordinates = match &xyz {
+        Some(xyz) if entry.late => match late_signature_coordinates(deps, &config, env, xyz)? {
+            Some(coordinates) => Some(coordinates),
+            None => {
+                violations.push(ContractError::LateSignatureUnavailable {});
+                xyz.extension.prev_coordinates
+            }
+        },
+        Some(xyz) => {
+            if !xyz.extension.has_arrived(env.block.time) {
+                violations.push(ContractError::RelocationInProgress {});
+            } else if env.block.time < stay_complete_at(&config, xyz) {
+                violations.push(ContractError::MinimumStayNotMet {});
+            }
+            Some(xyz.extension.coordinates)
+        }
+        None => None,
+    };
+
+    // Unless the entry is a reply, check that the xyz hasn't reached its cap on signatures at this
+    // location and that its cooldown since its latest signature here has passed, and find the new
+    // entry's index
+    let is_reply = entry.parent.is_some();
+    let index = match coordinates.map(|coordinates| {
+        next_entry_index(
+            deps.storage,
+            env,
+            &config,
+            author_xyz_id,
+            coordinates,
+            is_reply,
+        )
+    }) {
+        Some(Ok(index)) => Some(index),
+        Some(Err(err)) => {
+            violations.push(err);
+            None
+        }
+        None => None,
+    };
+
+    // Check that the payment covers the entry fee at this location. Replies pay the flat entry
+    // fee, since they don't count toward the location's pricing or its host's surcharge.
+    let (entry_fees, cw20_entry_fees) = match coordinates {
+        Some(coordinates) if !is_reply => quote_entry_fees(deps, &config, coordinates)?,
+        _ => (config.entry_fees.clone(), config.cw20_entry_fees.clone()),
+    };
+    let fee = match payment {
+        Payment::Native(funds) => deduct_entry_fee(&entry_fees, &funds)
+            .map(|(fee, refund)| FeeDeduction::Native { fee, refund }),
+        Payment::Cw20 {
+            token_contract,
+            amount,
+        } => deduct_cw20_entry_fee(&cw20_entry_fees, &token_contract, amount).map(|refund| {
+            FeeDeduction::Cw20 {
+                token_contract,
+                paid: amount - refund,
+                refund,
+            }
+        }),
+    };
+    let fee = match fee {
+        Ok(fee) => Some(fee),
+        Err(err) => {
+            violations.push(err);
+            None
+        }
+    };
+
+    Ok(match (coordinates, index, fee) {
+        (Some(coordinates), Some(index), Some(fee)) if violations.is_empty() => Ok(ValidEntry {
+            config,
+            coordinates,
+            index,
+            fee,
+        }),
+        _ => Err(violations),
+    })
+}
+
+// Describe an error that creating an entry would fail with as the rule the entry violates. The
+// author xyz lookup is the only check that fails with a StdError.
+fn violation_of(err: &ContractError) -> Violation {
+    match err {
+        ContractError::CharacterLimitExceeded { length, limit } => {
+            Violation::CharacterLimitExceeded {
+                length: *length,
+                limit: *limit,
+            }
+        }
+        ContractError::ContentPolicyViolation { rule } => {
+            Violation::ContentPolicyViolation { rule: rule.clone() }
+        }
+        ContractError::NotFound {} => Violation::ParentNotFound {},
+        ContractError::Std(_) => Violation::XyzNotFound {},
+        ContractError::Unauthorized {} => Violation::Unauthorized {},
+        ContractError::RelocationInProgress {} => Violation::RelocationInProgress {},
+        ContractError::MinimumStayNotMet {} => Violation::MinimumStayNotMet {},
+        ContractError::LateSignatureUnavailable {} => Violation::LateSignatureUnavailable {},
+        ContractError::EntryAlreadyExists {} => Violation::EntryCapReached {},
+        ContractError::EntryCooldownActive {} => Violation::EntryCooldownActive {},
+        ContractError::InsufficientFunds {} => Violation::InsufficientFunds {},
+        ContractError::WrongDenom {} => Violation::WrongDenom {},
+        err => Violation::Other {
+            error: err.to_string(),
+        },
+    }
+}
+
+// Create an entry for an xyz at its current location, or at its previous location for a late
+// signature, optionally in reply to another entry, charging the entry fee from the given payment
+// and refunding whatever is left over.
+fn create_entry(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    entry: NewEntry,
+    payment: Payment,
+) -> Result<Response, ContractError> {
+    // Check the entry against every rule for creating one, failing with the first violation
+    let ValidEntry {
+        config,
+        coordinates,
+        index,
+        fee,
+    } = check_new_entry(deps.as_ref(), &env, &sender, &entry, payment)?
+        .map_err(|mut violations| violations.remove(0))?;
+    let NewEntry {
+        author_xyz_id,
+        text,
+        parent,
+        late,
+    } = entry;
+
+    // Refund any overpayment and anything that wasn't used to pay the fee. Unless the entry is a
+    // reply, if the location has a host that is still there, the host's surcharge is forwarded
+    // to the current owner of the host xyz.
+    let host = match parent {
+        Some(_) => None,
+        None => match active_location(deps.as_ref(), &config, coordinates)? {
+            Some((location, host)) if location.surcharge_bps > 0 => {
+                Some((host.owner, location.surcharge_bps))
+            }
+            _ => None,
+        },
+    };
+    let mut res = Response::default();
+    match fee {
+        FeeDeduction::Native { fee, refund } => {
+            if !refund.is_empty() {
+                res = res.add_message(BankMsg::Send {
+                    to_address: sender.to_string(),
+                    amount: refund,
+                });
+            }
+            if let (Some((host_address, surcharge_bps)), Some(fee)) = (&host, fee) {
+                let surcharge = surcharge_of(fee.amount, *surcharge_bps);
+                if !surcharge.is_zero() {
+                    res = res.add_message(BankMsg::Send {
+                        to_address: host_address.to_string(),
+                        amount: vec![Coin {
+                            denom: fee.denom,
+                            amount: surcharge,
+                        }],
+                    });
+                }
+            }
+        }
+        FeeDeduction::Cw20 {
+            token_contract,
+            paid,
+            refund,
+        } => {
+            let token = Cw20Contract(token_contract);
+            if !refund.is_zero() {
+                res = res.add_message(token.call(Cw20ExecuteMsg::Transfer {
+                    recipient: sender.to_string(),
+                    amount: refund,
+                })?);
+            }
+            if let Some((host_address, surcharge_bps)) = &host {
+                let surcharge = surcharge_of(paid, *surcharge_bps);
+                if !surcharge.is_zero() {
+                    res = res.add_message(token.call(Cw20ExecuteMsg::Transfer {
+                        recipient: host_address.to_string(),
+                        amount: surcharge,
+                    })?);
+                }
+            }
+        }
+    }
+
+    // Assign the next sequence number
+    let seq = ENTRY_SEQ.may_load(deps.storage)?.unwrap_or_default() + 1;
+
+    // Construct the new entry
+    let new_entry = Entry {
+        author_xyz_id: author_xyz_id.clone(),
+        coordinates,
+        index,
+        text,
+        seq,
+        created_at: env.block.time,
+        block_height: env.block.height,
+        author_address: sender,
+        edit_count: 0,
+        last_edited: None,
+        tips: vec![],
+        pinned: false,
+        hidden_by_host: false,
+        hidden: false,
+        parent: parent.clone(),
+        reactions: vec![],
+        score: 0,
+        late,
+    };
+    let new_entry_key = entries_key(author_xyz_id.clone(), coordinates, index);
+
+    // Save the entry if one doesn't already exist with this key
+    entries().update(deps.storage, new_entry_key, |old_entry| match old_entry {
+        Some(_) => Err(ContractError::EntryAlreadyExists {}),
+        None => Ok(new_entry),
+    })?;
+    ENTRY_SEQ.save(deps.storage, &seq)?;
+
+    // Replies belong to their parent's thread, so only signatures count toward the location's
+    // entry count
+    if parent.is_none() {
+        LOCATION_ENTRY_COUNTS.update(
+            deps.storage,
+            &coordinates.to_bytes(),
+            |count| -> StdResult<_> { Ok(count.unwrap_or_default() + 1) },
+        )?;
+    }
+
+    // Record the entry in the xyz's posting history at the location, where a signature restarts
+    // the xyz's cooldown and counts toward its cap
+    POSTING_HISTORY.update(
+        deps.storage,
+        (&author_xyz_id, &coordinates.to_bytes()),
+        |history| -> StdResult<_> {
+            let history = history.unwrap_or_default();
+            Ok(match parent {
+                Some(_) => PostingHistory {
+                    next_index: index + 1,
+                    ..history
+                },
+                None => PostingHistory {
+                    next_index: index + 1,
+                    last_signed_at: Some(env.block.time),
+                    signature_count: history.signature_count + 1,
+                },
+            })
+        },
+    )?;
+
+    res = res
+        .add_attribute("action", "entry_created")
+        .add_attribute("xyz_id", author_xyz_id)
+        .add_attribute("xyz_coordinates_x", coordinates.x.to_string())
+        .add_attribute("xyz_coordinates_y", coordinates.y.to_string())
+        .add_attribute("xyz_coordinates_z", coordinates.z.to_string())
+        .add_attribute("entry_index", index.to_string());
+    if late {
+        res = res.add_attribute("late", "true");
+    }
+    if let Some(parent) = parent {
+        res = res
+            .add_attribute("parent_xyz_id", parent.author_xyz_id)
+            .add_attribute("parent_coordinates_x", parent.coordinates.x.to_string())
+            .add_attribute("parent_coordinates_y", parent.coordinates.y.to_string())
+            .add_attribute("parent_coordinates_z", parent.coordinates.z.to_string())
+            .add_attribute("parent_entry_index", parent.index.to_string());
+    }
+    Ok(res)
+}
+
+// Load an xyz's posting history at a location, which is empty if it has never posted there.
+fn posting_history(
+    storage: &dyn Storage,
+    author_xyz_id: &str,
+    coordinates: Coordinates,
+) -> StdResult<PostingHistory> {
+    Ok(POSTING_HISTORY
+        .may_load(storage, (author_xyz_id, &coordinates.to_bytes()))?
+        .unwrap_or_default())
+}
+
+// Find when the cooldown since an xyz's latest signature at a location ends, if it has signed.
+fn cooldown_ends_at(config: &Config, history: &PostingHistory) -> Option<Timestamp> {
+    history
+        .last_signed_at
+        .map(|last_signed_at| last_signed_at.plus_seconds(config.entry_cooldown_seconds))
+}
+
+// Find the index of an xyz's next entry at a location from its posting history there. Unless the
+// entry is a reply, check that the xyz is below its cap on signatures at the location and that
+// its cooldown since its latest signature there has passed, whether or not that was deleted.
+fn next_entry_index(
+    storage: &dyn Storage,
+    env: &Env,
+    config: &Config,
+    author_xyz_id: &str,
+    coordinates: Coordinates,
+    is_reply: bool,
+) -> Result<u32, ContractError> {
+    let history = posting_history(storage, author_xyz_id, coordinates)?;
+    if !is_reply {
+        if history.signature_count >= config.max_entries_per_location {
+            return Err(ContractError::EntryAlreadyExists {});
+        }
+        let cooldown_end = cooldown_ends_at(config, &history);
+        if matches!(cooldown_end, Some(cooldown_end) if env.block.time < cooldown_end) {
+            return Err(ContractError::EntryCooldownActive {});
+        }
+    }
+
+    // Replies and signatures share the xyz's indexes at the location, so keys never collide
+    Ok(history.next_index)
+}
+
+// Find the portion of a fee paid that went to the host's surcharge, rounded down.
+fn surcharge_of(paid: Uint128, surcharge_bps: u32) -> Uint128 {
+    paid.multiply_ratio(surcharge_bps, TOTAL_SHARE_BPS + surcharge_bps)
+}
+
+// Price the entry fees at a location, scaling each accepted fee by the configured pricing mode
+// according to the number of entries already there, then adding the surcharge of a host that is
+// still there.
+fn quote_entry_fees(
+    deps: Deps,
+    config: &Config,
+    coordinates: Coordinates,
+) -> StdResult<(Vec<Coin>, Vec<Cw20CoinVerified>)> {
+    let count = LOCATION_ENTRY_COUNTS
+        .may_load(deps.storage, &coordinates.to_bytes())?
+        .unwrap_or_default();
+    let surcharge_bps = active_location(deps, config, coordinates)?
+        .map_or(0, |(location, _)| location.surcharge_bps);
+    let price = |fee: Uint128| -> StdResult<Uint128> {
+        let price = config.pricing.price(fee, count)?;
+        Ok(price.checked_add(price.multiply_ratio(surcharge_bps, TOTAL_SHARE_BPS))?)
+    };
+
+    let entry_fees = config
+        .entry_fees
+        .iter()
+        .map(|fee| {
+            Ok(Coin {
+                denom: fee.denom.clone(),
+                amount: price(fee.amount)?,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    let cw20_entry_fees = config
+        .cw20_entry_fees
+        .iter()
+        .map(|fee| {
+            Ok(Cw20CoinVerified {
+                address: fee.address.clone(),
+                amount: price(fee.amount)?,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok((entry_fees, cw20_entry_fees))
+}
+
+pub fn execute_update_entry(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    entry_ref: EntryRef,
+    text: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // Check that text doesn't exceed the character limit and follows the content policy
+    assert_within_character_limit(&config, &text)?;
+    assert_content_allowed(deps.storage, &text)?;
+
+    let entry_key = entry_ref.key();
+    let old_entry = load_entry(deps.storage, entry_key.clone())?;
+
+    // Check that the sender still owns the author xyz
+    assert_xyz_owner(
+        deps.as_ref(),
+        &config,
+        &info.sender,
+        &entry_ref.author_xyz_id,
+    )?;
+
+    let new_entry = Entry {
+        text,
+        edit_count: old_entry.edit_count + 1,
+        last_edited: Some(env.block.time),
+        ..old_entry.clone()
+    };
+    entries().replace(deps.storage, entry_key, Some(&new_entry), Some(&old_entry))?;
+
+    Ok(Response::default()
+        .add_attribute("action", "entry_updated")
+        .add_attribute("xyz_id", entry_ref.author_xyz_id)
+        .add_attribute("xyz_coordinates_x", entry_ref.coordinates.x.to_string())
+        .add_attribute("xyz_coordinates_y", entry_ref.coordinates.y.to_string())
+        .add_attribute("xyz_coordinates_z", entry_ref.coordinates.z.to_string())
+        .add_attribute("entry_index", entry_ref.index.to_string()))
+}
+
+pub fn execute_delete_entry(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    entry_ref: EntryRef,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    let entry_key = entry_ref.key();
+    let entry = load_entry(deps.storage, entry_key.clone())?;
+
+    // Check that the sender still owns the author xyz
+    assert_xyz_owner(
+        deps.as_ref(),
+        &config,
+        &info.sender,
+        &entry_ref.author_xyz_id,
+    )?;
+
+    // Removing through the indexed map also removes the entry from every index
+    entries().remove(deps.storage, entry_key.clone())?;
+    clear_reports(deps.storage, &entry_key)?;
+
+    // Deleting a signature frees up a spot under the xyz's cap at the location, but its index
+    // and cooldown stay in the xyz's posting history
+    if entry.parent.is_none() {
+        LOCATION_ENTRY_COUNTS.update(
+            deps.storage,
+            &entry_ref.coordinates.to_bytes(),
+            |count| -> StdResult<_> { Ok(count.unwrap_or_default().saturating_sub(1)) },
+        )?;
+        POSTING_HISTORY.update(
+            deps.storage,
+            (&entry_ref.author_xyz_id, &entry_ref.coordinates.to_bytes()),
+            |history| -> StdResult<_> {
+                let history = history.unwrap_or_default();
+                Ok(PostingHistory {
+                    signature_count: history.signature_count.saturating_sub(1),
+                    ..history
+                })
+            },
+        )?;
+    }
+
+    Ok(Response::default()
+        .add_attribute("action", "entry_deleted")
+        .add_attribute("xyz_id", entry_ref.author_xyz_id)
+        .add_attribute("xyz_coordinates_x", entry_ref.coordinates.x.to_string())
+        .add_attribute("xyz_coordinates_y", entry_ref.coordinates.y.to_string())
+        .add_attribute("xyz_coordinates_z", entry_ref.coordinates.z.to_string())
+        .add_attribute("entry_index", entry_ref.index.to_string()))
+}
+
+pub fn execute_tip_entry(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    entry_ref: EntryRef,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    if info.funds.iter().all(|coin| coin.amount.is_zero()) {
+        return Err(ContractError::InsufficientFunds {});
+    }
+
+    let entry_key = entry_ref.key();
+    let old_entry = load_entry(deps.storage, entry_key.clone())?;
+
+    // Tips go to whoever owns the author xyz now, not whoever wrote the entry
+    let xyz = query_xyz(deps.as_ref(), &config, &entry_ref.author_xyz_id)?;
+
+    // Keep the protocol's cut and forward the rest of each coin
+    let mut forwarded = vec![];
+    let mut tips = old_entry.tips.clone();
+    for coin in info.funds.iter().filter(|coin| !coin.amount.is_zero()) {
+        let cut = coin
+            .amount
+            .multiply_ratio(config.tip_fee_bps, TOTAL_SHARE_BPS);
+        if coin.amount > cut {
+            forwarded.push(Coin {
+                denom: coin.denom.clone(),
+                amount: coin.amount - cut,
+            });
+        }
+        match tips.iter_mut().find(|tip| tip.denom == coin.denom) {
+            Some(tip) => tip.amount += coin.amount,
+            None => tips.push(coin.clone()),
+        }
+    }
+
+    let new_entry = Entry {
+        tips,
+        ..old_entry.clone()
+    };
+    entries().replace(deps.storage, entry_key, Some(&new_entry), Some(&old_entry))?;
+
+    let mut res = Response::default();
+    if !forwarded.is_empty() {
+        res = res.add_message(BankMsg::Send {
+            to_address: xyz.owner.to_string(),
+            amount: forwarded,
+        });
+    }
+
+    Ok(res
+        .add_attribute("action", "entry_tipped")
+        .add_attribute("xyz_id", entry_ref.author_xyz_id)
+        .add_attribute("xyz_coordinates_x", entry_ref.coordinates.x.to_string())
+        .add_attribute("xyz_coordinates_y", entry_ref.coordinates.y.to_string())
+        .add_attribute("xyz_coordinates_z", entry_ref.coordinates.z.to_string())
+        .add_attribute("entry_index", entry_ref.index.to_string())
+        .add_attribute("recipient", xyz.owner))
+}
+
+pub fn execute_react_to_entry(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    entry_ref: EntryRef,
+    reaction: String,
+    reactor_xyz_id: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if !config.allowed_reactions.contains(&reaction) {
+        return Err(ContractError::InvalidReaction {});
+    }
+
+    let entry_key = entry_ref.key();
+    let old_entry = load_entry(deps.storage, entry_key.clone())?;
+
+    // Check that the sender owns the reacting xyz
+    assert_xyz_owner(deps.as_ref(), &config, &info.sender, &reactor_xyz_id)?;
+
+    // Each xyz can leave each reaction on an entry only once
+    let reaction_key = (
+        U64Key::new(old_entry.seq),
+        reactor_xyz_id.as_str(),
+        reaction.as_str(),
+    );
+    if REACTIONS.has(deps.storage, reaction_key.clone()) {
+        return Err(ContractError::AlreadyReacted {});
+    }
+    REACTIONS.save(deps.storage, reaction_key, &Empty {})?;
+
+    let mut new_entry = old_entry.clone();
+    match new_entry
+        .reactions
+        .iter_mut()
+        .find(|count| count.reaction == reaction)
+    {
+        Some(count) => count.count += 1,
+        None => new_entry.reactions.push(ReactionCount {
+            reaction: reaction.clone(),
+            count: 1,
+        }),
+    }
+    new_entry.score += 1;
+    entries().replace(deps.storage, entry_key, Some(&new_entry), Some(&old_entry))?;
+
+    Ok(Response::default()
+        .add_attribute("action", "entry_reacted")
+        .add_attribute("xyz_id", entry_ref.author_xyz_id)
+        .add_attribute("xyz_coordinates_x", entry_ref.coordinates.x.to_string())
+        .add_attribute("xyz_coordinates_y", entry_ref.coordinates.y.to_string())
+        .add_attribute("xyz_coordinates_z", entry_ref.coordinates.z.to_string())
+        .add_attribute("entry_index", entry_ref.index.to_string())
+        .add_attribute("reactor_xyz_id", reactor_xyz_id)
+        .add_attribute("reaction", reaction))
+}
+
+pub fn execute_claim_location(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    xyz_id: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // Look up the xyz and check that the sender owns it and that it has arrived at its location
+    let xyz = assert_xyz_owner(deps.as_ref(), &config, &info.sender, &xyz_id)?;
+    if !xyz.extension.has_arrived(env.block.time) {
+        return Err(ContractError::RelocationInProgress {});
+    }
+    let coordinates = xyz.extension.coordinates;
+
+    // A claimed location can only be taken over once its host xyz has moved away
+    if active_location(deps.as_ref(), &config, coordinates)?.is_some() {
+        return Err(ContractError::LocationAlreadyClaimed {});
+    }
+
+    LOCATIONS.save(
+        deps.storage,
+        &coordinates.to_bytes(),
+        &Location {
+            host_xyz_id: xyz_id.clone(),
+            surcharge_bps: 0,
+        },
+    )?;
+
+    Ok(Response::default()
+        .add_attribute("action", "location_claimed")
+        .add_attribute("xyz_id", xyz_id)
+        .add_attribute("xyz_coordinates_x", coordinates.x.to_string())
+        .add_attribute("xyz_coordinates_y", coordinates.y.to_string())
+        .add_attribute("xyz_coordinates_z", coordinates.z.to_string()))
+}
+
+// Load a location along with its host xyz, as long as the host xyz is still there. A host that
+// has moved away keeps its claim on paper, but loses its powers and its surcharge until it
+// returns or another xyz takes the location over.
+fn active_location(
+    deps: Deps,
+    config: &Config,
+    coordinates: Coordinates,
+) -> StdResult<Option<(Location, XyzTokenInfo)>> {
+    let location = match LOCATIONS.may_load(deps.storage, &coordinates.to_bytes())? {
+        Some(location) => location,
+        None => return Ok(None),
+    };
+    let host = query_xyz(deps, config, &location.host_xyz_id)?;
+    Ok(if host.extension.coordinates == coordinates {
+        Some((location, host))
+    } else {
+        None
+    })
+}
+
+// Check that the sender owns the xyz hosting the given location, and that the host xyz is still
+// there, returning the location.
+fn assert_location_host(
+    deps: Deps,
+    config: &Config,
+    sender: &Addr,
+    coordinates: Coordinates,
+) -> Result<Location, ContractError> {
+    let (location, host) =
+        active_location(deps, config, coordinates)?.ok_or(ContractError::Unauthorized {})?;
+    if host.owner != *sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(location)
+}
+
+pub fn execute_set_location_surcharge(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    coordinates: Coordinates,
+    surcharge_bps: u32,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let location = assert_location_host(deps.as_ref(), &config, &info.sender, coordinates)?;
+
+    // The surcharge can't be more than the entry fee itself
+    if surcharge_bps > TOTAL_SHARE_BPS {
+        return Err(ContractError::InvalidSurcharge {});
+    }
+    LOCATIONS.save(
+        deps.storage,
+        &coordinates.to_bytes(),
+        &Location {
+            surcharge_bps,
+            ..location
+        },
+    )?;
+
+    Ok(Response::default()
+        .add_attribute("action", "location_surcharge_set")
+        .add_attribute("xyz_coordinates_x", coordinates.x.to_string())
+        .add_attribute("xyz_coordinates_y", coordinates.y.to_string())
+        .add_attribute("xyz_coordinates_z", coordinates.z.to_string())
+        .add_attribute("surcharge_bps", surcharge_bps.to_string()))
+}
+
+pub fn execute_set_entry_pinned(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    entry_ref: EntryRef,
+    pinned: bool,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    assert_location_host(deps.as_ref(), &config, &info.sender, entry_ref.coordinates)?;
+
+    let entry_key = entry_ref.key();
+    let old_entry = load_entry(deps.storage, entry_key.clone())?;
+    let new_entry = Entry {
+        pinned,
+        ..old_entry.clone()
+    };
+    entries().replace(deps.storage, entry_key, Some(&new_entry), Some(&old_entry))?;
+
+    Ok(Response::default()
+        .add_attribute("action", "entry_pinned")
+        .add_attribute("xyz_id", entry_ref.author_xyz_id)
+        .add_attribute("xyz_coordinates_x", entry_ref.coordinates.x.to_string())
+        .add_attribute("xyz_coordinates_y", entry_ref.coordinates.y.to_string())
+        .add_attribute("xyz_coordinates_z", entry_ref.coordinates.z.to_string())
+        .add_attribute("entry_index", entry_ref.index.to_string())
+        .add_attribute("pinned", pinned.to_string()))
+}
+
+pub fn execute_set_entry_hidden_by_host(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    entry_ref: EntryRef,
+    hidden: bool,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    assert_location_host(deps.as_ref(), &config, &info.sender, entry_ref.coordinates)?;
+
+    let entry_key = entry_ref.key();
+    let old_entry = load_entry(deps.storage, entry_key.clone())?;
+    let new_entry = Entry {
+        hidden_by_host: hidden,
+        ..old_entry.clone()
+    };
+    entries().replace(deps.storage, entry_key, Some(&new_entry), Some(&old_entry))?;
+
+    Ok(Response::default()
+        .add_attribute("action", "entry_hidden_by_host")
+        .add_attribute("xyz_id", entry_ref.author_xyz_id)
+        .add_attribute("xyz_coordinates_x", entry_ref.coordinates.x.to_string())
+        .add_attribute("xyz_coordinates_y", entry_ref.coordinates.y.to_string())
+        .add_attribute("xyz_coordinates_z", entry_ref.coordinates.z.to_string())
+        .add_attribute("entry_index", entry_ref.index.to_string())
+        .add_attribute("hidden", hidden.to_string()))
+}
+
+pub fn execute_update_moderators(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    add: Vec<String>,
+    remove: Vec<String>,
+) -> Result<Response, ContractError> {
+    assert_owner(deps.as_ref(), &info)?;
+
+    let add = add
+        .iter()
+        .map(|address| deps.api.addr_validate(address))
+        .collect::<StdResult<Vec<_>>>()?;
+    let remove = remove
+        .iter()
+        .map(|address| deps.api.addr_validate(address))
+        .collect::<StdResult<Vec<_>>>()?;
+    for moderator in add.iter() {
+        MODERATORS.save(deps.storage, moderator, &Empty {})?;
+    }
+    for moderator in remove.iter() {
+        MODERATORS.remove(deps.storage, moderator);
+    }
+
+    let join = |addresses: Vec<Addr>| {
+        addresses
+            .into_iter()
+            .map(String::from)
+            .collect::<Vec<_>>()
+            .join(",")
+    };
+    Ok(Response::default()
+        .add_attribute("action", "moderators_updated")
+        .add_attribute("added", join(add))
+        .add_attribute("removed", join(remove)))
+}
+
+pub fn execute_report_entry(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    entry_ref: EntryRef,
+    reporter_xyz_id: String,
+    reason: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // Reasons are held to the same character limit as entry text
+    assert_within_character_limit(&config, &reason)?;
+
+    // Check that the entry exists and that the sender owns the reporter xyz
+    load_entry(deps.storage, entry_ref.key())?;
+    assert_xyz_owner(deps.as_ref(), &config, &info.sender, &reporter_xyz_id)?;
+
+    let id = REPORT_SEQ.may_load(deps.storage)?.unwrap_or_default() + 1;
+    let report = Report {
+        id,
+        author_xyz_id: entry_ref.author_xyz_id.clone(),
+        coordinates: entry_ref.coordinates,
+        index: entry_ref.index,
+        reporter_xyz_id: reporter_xyz_id.clone(),
+        reporter_address: info.sender,
+        reason,
+        created_at: env.block.time,
+    };
+    reports().save(deps.storage, U64Key::new(id), &report)?;
+    REPORT_SEQ.save(deps.storage, &id)?;
+
+    Ok(Response::default()
+        .add_attribute("action", "entry_reported")
+        .add_attribute("report_id", id.to_string())
+        .add_attribute("xyz_id", entry_ref.author_xyz_id)
+        .add_attribute("xyz_coordinates_x", entry_ref.coordinates.x.to_string())
+        .add_attribute("xyz_coordinates_y", entry_ref.coordinates.y.to_string())
+        .add_attribute("xyz_coordinates_z", entry_ref.coordinates.z.to_string())
+        .add_attribute("entry_index", entry_ref.index.to_string())
+        .add_attribute("reporter_xyz_id", reporter_xyz_id))
+}
+
+// Remove every open report on the given entry from the moderation queue.
+fn clear_reports(storage: &mut dyn Storage, entry_key: &EntryKey) -> StdResult<()> {
+    let ids = reports()
+        .idx
+        .entry
+        .prefix(entry_key.joined_key())
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<Vec<_>>();
+    for id in ids {
+        reports().remove(storage, U64Key::from(id))?;
+    }
+    Ok(())
+}
+
+pub fn execute_set_entry_hidden(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    entry_ref: EntryRef,
+    hidden: bool,
+) -> Result<Response, ContractError> {
+    if !MODERATORS.has(deps.storage, &info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let entry_key = entry_ref.key();
+    let old_entry = load_entry(deps.storage, entry_key.clone())?;
+    let new_entry = Entry {
+        hidden,
+        ..old_entry.clone()
+    };
+    entries().replace(
+        deps.storage,
+        entry_key.clone(),
+        Some(&new_entry),
+        Some(&old_entry),
+    )?;
+
+    // Either way the entry's reports have now been dealt with
+    clear_reports(deps.storage, &entry_key)?;
+
+    Ok(Response::default()
+        .add_attribute(
+            "action",
+            if hidden {
+                "entry_hidden"
+            } else {
+                "entry_restored"
+            },
+        )
+        .add_attribute("xyz_id", entry_ref.author_xyz_id)
+        .add_attribute("xyz_coordinates_x", entry_ref.coordinates.x.to_string())
+        .add_attribute("xyz_coordinates_y", entry_ref.coordinates.y.to_string())
+        .add_attribute("xyz_coordinates_z", entry_ref.coordinates.z.to_string())
+        .add_attribute("entry_index", entry_ref.index.to_string()))
+}
+
+pub fn execute_withdraw(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    amount: Vec<Coin>,
+) -> Result<Response, ContractError> {
+    let owner = assert_owner(deps.as_ref(), &info)?;
+
+    // While payees are configured, the native balance is split between them by Distribute
+    if has_payees(deps.as_ref())? {
+        return Err(ContractError::PayeesConfigured {});
+    }
+
+    Ok(Response::default().add_message(BankMsg::Send {
+        amount,
+        to_address: owner,
+    }))
+}
+
+pub fn execute_withdraw_cw20(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    token_contract: String,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let owner = assert_owner(deps.as_ref(), &info)?;
+
+    let token_contract = deps.api.addr_validate(&token_contract)?;
+
+    // While payees are configured, accepted fee tokens are split between them by Distribute.
+    // Tokens that were sent to the contract by other means can still be withdrawn.
+    let config = CONFIG.load(deps.storage)?;
+    if has_payees(deps.as_ref())?
+        && config
+            .cw20_entry_fees
+            .iter()
+            .any(|fee| fee.address == token_contract)
+    {
+        return Err(ContractError::PayeesConfigured {});
+    }
+
+    Ok(
+        Response::default().add_message(Cw20Contract(token_contract).call(
+            Cw20ExecuteMsg::Transfer {
+                recipient: owner,
+                amount,
+            },
+        )?),
+    )
+}
+
+pub fn execute_update_config(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: UpdateConfigMsg,
+) -> Result<Response, ContractError> {
+    assert_owner(deps.as_ref(), &info)?;
+
+    let old_config = CONFIG.load(deps.storage)?;
+    let mut config = old_config.clone();
+    let mut res = Response::default().add_attribute("action", "config_updated");
+
+    if let Some(xyz_nft_contract) = msg.xyz_nft_contract {
+        config.xyz_nft_contract = deps.api.addr_validate(&xyz_nft_contract)?;
+        res = res
+            .add_attribute("old_xyz_nft_contract", old_config.xyz_nft_contract)
+            .add_attribute("new_xyz_nft_contract", config.xyz_nft_contract.clone());
+    }
+
+    if let Some(character_limit) = msg.character_limit {
+        config.character_limit = character_limit;
+        res = res
+            .add_attribute(
+                "old_character_limit",
+                old_config.character_limit.to_string(),
+            )
+            .add_attribute("new_character_limit", character_limit.to_string());
+    }
+
+    if let Some(limit_mode) = msg.limit_mode {
+        res = res
+            .add_attribute("old_limit_mode", old_config.limit_mode.to_string())
+            .add_attribute("new_limit_mode", limit_mode.to_string());
+        config.limit_mode = limit_mode;
+    }
+
+    if let Some(entry_fees) = msg.entry_fees {
+        res = res
+            .add_attribute("old_entry_fees", coins_to_string(&old_config.entry_fees))
+            .add_attribute("new_entry_fees", coins_to_string(&entry_fees));
+        config.entry_fees = entry_fees;
+    }
+
+    if let Some(cw20_entry_fees) = msg.cw20_entry_fees {
+        let cw20_entry_fees = cw20_entry_fees
+            .into_iter()
+            .map(|fee| {
+                Ok(Cw20CoinVerified {
+                    address: deps.api.addr_validate(&fee.address)?,
+                    amount: fee.amount,
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+        res = res
+            .add_attribute(
+                "old_cw20_entry_fees",
+                cw20_coins_to_string(&old_config.cw20_entry_fees),
+            )
+            .add_attribute(
+                "new_cw20_entry_fees",
+                cw20_coins_to_string(&cw20_entry_fees),
+            );
+        config.cw20_entry_fees = cw20_entry_fees;
+    }
+
+    if let Some(tip_fee_bps) = msg.tip_fee_bps {
+        config.tip_fee_bps = tip_fee_bps;
+        res = res
+            .add_attribute("old_tip_fee_bps", old_config.tip_fee_bps.to_string())
+            .add_attribute("new_tip_fee_bps", tip_fee_bps.to_string());
+    }
+
+    if let Some(pricing) = msg.pricing {
+        res = res
+            .add_attribute("old_pricing", old_config.pricing.to_string())
+            .add_attribute("new_pricing", pricing.to_string());
+        config.pricing = pricing;
+    }
+
+    if let Some(allowed_reactions) = msg.allowed_reactions {
+        res = res
+            .add_attribute(
+                "old_allowed_reactions",
+                old_config.allowed_reactions.join(","),
+            )
+            .add_attribute("new_allowed_reactions", allowed_reactions.join(","));
+        config.allowed_reactions = allowed_reactions;
+    }
+
+    if let Some(max_entries_per_location) = msg.max_entries_per_location {
+        res = res
+            .add_attribute(
+                "old_max_entries_per_location",
+                old_config.max_entries_per_location.to_string(),
+            )
+            .add_attribute(
+                "new_max_entries_per_location",
+                max_entries_per_location.to_string(),
+            );
+        config.max_entries_per_location = max_entries_per_location;
+    }
+
+    if let Some(entry_cooldown_seconds) = msg.entry_cooldown_seconds {
+        res = res
+            .add_attribute(
+                "old_entry_cooldown_seconds",
+                old_config.entry_cooldown_seconds.to_string(),
+            )
+            .add_attribute(
+                "new_entry_cooldown_seconds",
+                entry_cooldown_seconds.to_string(),
+            );
+        config.entry_cooldown_seconds = entry_cooldown_seconds;
+    }
+
+    if let Some(min_stay_seconds) = msg.min_stay_seconds {
+        res = res
+            .add_attribute(
+                "old_min_stay_seconds",
+                old_config.min_stay_seconds.to_string(),
+            )
+            .add_attribute("new_min_stay_seconds", min_stay_seconds.to_string());
+        config.min_stay_seconds = min_stay_seconds;
+    }
+
+    if let Some(late_signature_grace_seconds) = msg.late_signature_grace_seconds {
+        res = res
+            .add_attribute(
+                "old_late_signature_grace_seconds",
+                old_config.late_signature_grace_seconds.to_string(),
+            )
+            .add_attribute(
+                "new_late_signature_grace_seconds",
+                late_signature_grace_seconds.to_string(),
+            );
+        config.late_signature_grace_seconds = late_signature_grace_seconds;
+    }
+
+    validate_config(deps.as_ref(), &config)?;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(res)
+}
+
+// Check the invariants every stored config must satisfy, shared by
+// instantiate and UpdateConfig.
+fn validate_config(deps: Deps, config: &Config) -> Result<(), ContractError> {
+    deps.api.addr_validate(config.xyz_nft_contract.as_str())?;
+
+    // A zero character limit would make it impossible to create entries
+    if config.character_limit == 0 {
+        return Err(ContractError::InvalidCharacterLimit {});
+    }
+
+    // A zero cap would make it impossible to create entries
+    if config.max_entries_per_location == 0 {
+        return Err(ContractError::InvalidMaxEntries {});
+    }
+
+    // The protocol can't take more than the whole tip
+    if config.tip_fee_bps > TOTAL_SHARE_BPS {
+        return Err(ContractError::InvalidTipFee {});
+    }
+
+    // An empty reaction couldn't be told apart from no reaction at all
+    if config.allowed_reactions.iter().any(String::is_empty) {
+        return Err(ContractError::InvalidReaction {});
+    }
+
+    for fee in &config.cw20_entry_fees {
+        deps.api.addr_validate(fee.address.as_str())?;
+    }
+
+    Ok(())
+}
+
+// Format a list of coins for an attribute value, e.g., "1000uluna,1000uusd".
+fn coins_to_string(coins: &[Coin]) -> String {
+    coins
+        .iter()
+        .map(|coin| coin.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+// Format a list of CW20 token amounts for an attribute value, e.g., "1000:terra1...".
+fn cw20_coins_to_string(coins: &[Cw20CoinVerified]) -> String {
+    coins
+        .iter()
+        .map(|coin| format!("{}:{}", coin.amount, coin.address))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+pub fn execute_update_content_policy(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    policy: ContentPolicy,
+) -> Result<Response, ContractError> {
+    assert_owner(deps.as_ref(), &info)?;
+
+    // An empty banned substring would match, and so ban, every entry
+    if policy.banned_substrings.iter().any(String::is_empty) {
+        return Err(ContractError::InvalidContentPolicy {});
+    }
+    CONTENT_POLICY.save(deps.storage, &policy)?;
+
+    Ok(Response::default()
+        .add_attribute("action", "content_policy_updated")
+        .add_attribute(
+            "banned_substrings",
+            policy.banned_substrings.len().to_string(),
+        )
+        .add_attribute(
+            "disallowed_characters",
+            policy
+                .disallowed_characters
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(","),
+        )
+        .add_attribute(
+            "max_newlines",
+            policy
+                .max_newlines
+                .map_or_else(|| "none".to_string(), |max| max.to_string()),
+        )
+        .add_attribute("block_urls", policy.block_urls.to_string()))
+}
+
+// 100% in basis points, which all payees' shares add up to.
+const TOTAL_SHARE_BPS: u32 = 10000;
+
+pub fn execute_update_payees(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    payees: Vec<PayeeMsg>,
+) -> Result<Response, ContractError> {
+    assert_owner(deps.as_ref(), &info)?;
+
+    let payees = payees
+        .into_iter()
+        .map(|payee| {
+            Ok(Payee {
+                address: deps.api.addr_validate(&payee.address)?,
+                share_bps: payee.share_bps,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    // Check that every payee has a share, each payee is listed once, and the shares add up to
+    // 100%, unless payees are being cleared
+    if !payees.is_empty() {
+        let total_share_bps = payees
+            .iter()
+            .fold(0u64, |total, payee| total + u64::from(payee.share_bps));
+        let has_duplicates = payees.iter().enumerate().any(|(i, payee)| {
+            payees[..i]
+                .iter()
+                .any(|other| other.address == payee.address)
+        });
+        if total_share_bps != u64::from(TOTAL_SHARE_BPS)
+            || has_duplicates
+            || payees.iter().any(|payee| payee.share_bps == 0)
+        {
+            return Err(ContractError::InvalidPayeeShares {});
+        }
+    }
+
+    PAYEES.save(deps.storage, &payees)?;
+
+    Ok(Response::default()
+        .add_attribute("action", "payees_updated")
+        .add_attribute(
+            "payees",
+            payees
+                .iter()
+                .map(|payee| format!("{}:{}", payee.address, payee.share_bps))
+                .collect::<Vec<_>>()
+                .join(","),
+        ))
+}
+
+// Split an amount between payees in proportion to their shares. Each share is rounded down, and
+// the remainder left over from rounding goes to the first payee, so the whole amount is always
+// paid out and the result doesn't depend on anything but the amount and the payees.
+fn split_amount(amount: Uint128, payees: &[Payee]) -> Vec<Uint128> {
+    let mut shares = payees
+        .iter()
+        .map(|payee| amount.multiply_ratio(payee.share_bps, TOTAL_SHARE_BPS))
+        .collect::<Vec<_>>();
+    let remainder = amount - shares.iter().fold(Uint128::zero(), |sum, s| sum + *s);
+    if let Some(first) = shares.first_mut() {
+        *first += remainder;
+    }
+    shares
+}
+
+// Split a native balance between payees, leaving out shares that round down to nothing.
+fn split_balance(balance: &[Coin], payees: &[Payee]) -> Vec<Vec<Coin>> {
+    let mut split = vec![vec![]; payees.len()];
+    for coin in balance {
+        for (i, share) in split_amount(coin.amount, payees).into_iter().enumerate() {
+            if !share.is_zero() {
+                split[i].push(Coin {
+                    denom: coin.denom.clone(),
+                    amount: share,
+                });
+            }
+        }
+    }
+    split
+}
+
+// Split a CW20 balance between payees, leaving out shares that round down to nothing.
+fn split_cw20_balance(
+    balance: &[Cw20CoinVerified],
+    payees: &[Payee],
+) -> Vec<Vec<Cw20CoinVerified>> {
+    let mut split = vec![vec![]; payees.len()];
+    for coin in balance {
+        for (i, share) in split_amount(coin.amount, payees).into_iter().enumerate() {
+            if !share.is_zero() {
+                split[i].push(Cw20CoinVerified {
+                    address: coin.address.clone(),
+                    amount: share,
+                });
+            }
+        }
+    }
+    split
+}
+
+// Query the contract's balance of each CW20 token currently accepted for entry fees. Tokens that
+// are no longer accepted aren't distributed, but the owner can still withdraw them.
+fn query_cw20_balance(deps: Deps, env: &Env) -> StdResult<Vec<Cw20CoinVerified>> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut balance = vec![];
+    for fee in config.cw20_entry_fees {
+        let res: BalanceResponse = deps.querier.query_wasm_smart(
+            fee.address.clone(),
+            &Cw20QueryMsg::Balance {
+                address: env.contract.address.to_string(),
+            },
+        )?;
+        if !res.balance.is_zero() {
+            balance.push(Cw20CoinVerified {
+                address: fee.address,
+                amount: res.balance,
+            });
+        }
+    }
+    Ok(balance)
+}
+
+fn has_payees(deps: Deps) -> StdResult<bool> {
+    Ok(!PAYEES
+        .may_load(deps.storage)?
+        .unwrap_or_default()
+        .is_empty())
+}
+
+pub fn execute_distribute(
+    deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let payees = PAYEES.may_load(deps.storage)?.unwrap_or_default();
+    if payees.is_empty() {
+        return Err(ContractError::NotFound {});
+    }
+
+    // Anyone may trigger a distribution, since funds only ever go to the configured payees. Both
+    // the native balance and the balance of each accepted CW20 token are split between them.
+    let balance = deps
+        .querier
+        .query_all_balances(env.contract.address.clone())?;
+    let cw20_balance = query_cw20_balance(deps.as_ref(), &env)?;
+    let mut res = Response::default();
+    for ((payee, amount), cw20_amount) in payees
+        .iter()
+        .zip(split_balance(&balance, &payees))
+        .zip(split_cw20_balance(&cw20_balance, &payees))
+    {
+        if !amount.is_empty() {
+            res = res.add_message(BankMsg::Send {
+                to_address: payee.address.to_string(),
+                amount,
+            });
+        }
+        for coin in cw20_amount {
+            res = res.add_message(Cw20Contract(coin.address).call(Cw20ExecuteMsg::Transfer {
+                recipient: payee.address.to_string(),
+                amount: coin.amount,
+            })?);
+        }
+    }
+
+    Ok(res.add_attribute("action", "fees_distributed"))
+}
+
+pub fn execute_propose_new_owner(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    new_owner: String,
+) -> Result<Response, ContractError> {
+    assert_owner(deps.as_ref(), &info)?;
+
+    let new_owner = deps.api.addr_validate(&new_owner)?;
+    PENDING_OWNER.save(deps.storage, &new_owner.to_string())?;
+
+    Ok(Response::default()
+        .add_attribute("action", "ownership_proposed")
+        .add_attribute("pending_owner", new_owner))
+}
+
+pub fn execute_accept_ownership(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
-    msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
-    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    // Check that the sender is the proposed owner
+    match PENDING_OWNER.may_load(deps.storage)? {
+        Some(pending_owner) if info.sender == pending_owner => {}
+        _ => return Err(ContractError::Unauthorized {}),
+    }
 
-    CONFIG.save(deps.storage, &msg.config)?;
     OWNER.save(deps.storage, &info.sender.to_string())?;
+    PENDING_OWNER.remove(deps.storage);
 
     Ok(Response::default()
-        .add_attribute("method", "instantiate")
+        .add_attribute("action", "ownership_accepted")
         .add_attribute("owner", info.sender))
 }
 
-#[entry_point]
-pub fn execute(
+pub fn execute_cancel_ownership_proposal(
     deps: DepsMut,
-    env: Env,
+    _env: Env,
     info: MessageInfo,
-    msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
-    match msg {
-        ExecuteMsg::CreateEntry {
-            author_xyz_id,
-            text,
-        } => execute_create_entry(deps, env, info, author_xyz_id, text),
-        ExecuteMsg::Withdraw { amount } => execute_withdraw(deps, env, info, amount),
+    assert_owner(deps.as_ref(), &info)?;
+
+    if PENDING_OWNER.may_load(deps.storage)?.is_none() {
+        return Err(ContractError::NotFound {});
     }
+    PENDING_OWNER.remove(deps.storage);
+
+    Ok(Response::default().add_attribute("action", "ownership_proposal_cancelled"))
 }
 
-pub fn execute_create_entry(
+pub fn execute_renounce_ownership(
     deps: DepsMut,
-    env: Env,
+    _env: Env,
     info: MessageInfo,
-    author_xyz_id: String,
-    text: String,
 ) -> Result<Response, ContractError> {
-    let config = CONFIG.load(deps.storage)?;
+    assert_owner(deps.as_ref(), &info)?;
 
-    // Check that text doesn't exceed the character limit
-    if text.len() > config.character_limit as usize {
-        return Err(ContractError::CharacterLimitExceeded {});
-    }
+    OWNER.remove(deps.storage);
+    PENDING_OWNER.remove(deps.storage);
 
-    // Check that the sender provided funds sufficient to cover the entry fee
-    if !info.funds.iter().any(|coin| {
-        // the denomination matches and the amount is sufficient
-        coin.denom == config.entry_fee.denom && coin.amount.u128() >= config.entry_fee.amount.u128()
-    }) {
-        return Err(ContractError::InsufficientFunds {});
-    }
+    Ok(Response::default().add_attribute("action", "ownership_renounced"))
+}
 
-    // Look up the author xyz
-    let xyz: XyzTokenInfo = deps.querier.query_wasm_smart(
-        config.xyz_nft_contract,
-        &XyzQueryMsg::XyzNftInfo {
-            token_id: author_xyz_id.clone(),
-        },
-    )?;
+#[entry_point]
+pub fn migrate(deps: DepsMut, env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let stored = get_contract_version(deps.storage)?;
 
-    // Check that the sender owns this xyz
-    if xyz.owner != info.sender {
-        return Err(ContractError::Unauthorized {});
+    // Check that we're migrating from a deployment of this same contract
+    if stored.contract != CONTRACT_NAME {
+        return Err(ContractError::CannotMigrate {
+            previous_contract: stored.contract,
+        });
     }
 
-    // Check that the xyz isn't currently relocating
-    if !xyz.extension.has_arrived(env.block.time) {
-        return Err(ContractError::RelocationInProgress {});
+    // Check that we're not migrating to an older version
+    let previous_version = parse_version(&stored.version)?;
+    let new_version = parse_version(CONTRACT_VERSION)?;
+    if previous_version > new_version {
+        return Err(ContractError::CannotDowngrade {
+            previous_version: stored.version,
+            new_version: CONTRACT_VERSION.to_string(),
+        });
     }
 
-    // Construct the new entry
-    let new_entry = Entry {
-        author_xyz_id: author_xyz_id.clone(),
-        coordinates: xyz.extension.coordinates,
-        text,
-    };
-    let new_entry_key = entries_key(author_xyz_id.clone(), xyz.extension.coordinates);
+    // Apply every data migration introduced since the stored version
+    let applied = run_migrations(deps.storage, &env, &previous_version)?;
 
-    // Save the entry if one doesn't already exist for this xyz at this location
-    entries().update(deps.storage, &new_entry_key, |old_entry| match old_entry {
-        Some(_) => Err(ContractError::EntryAlreadyExists {}),
-        None => Ok(new_entry),
-    })?;
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
     Ok(Response::default()
-        .add_attribute("action", "entry_created")
-        .add_attribute("xyz_id", author_xyz_id)
-        .add_attribute("xyz_coordinates_x", xyz.extension.coordinates.x.to_string())
-        .add_attribute("xyz_coordinates_y", xyz.extension.coordinates.y.to_string())
-        .add_attribute("xyz_coordinates_z", xyz.extension.coordinates.z.to_string()))
-}
-
-pub fn execute_withdraw(
-    deps: DepsMut,
-    _env: Env,
-    info: MessageInfo,
-    amount: Vec<Coin>,
-) -> Result<Response, ContractError> {
-    let owner = OWNER.load(deps.storage)?;
-    if info.sender != owner {
-        return Err(ContractError::Unauthorized {});
-    }
-
-    Ok(Response::default().add_message(BankMsg::Send {
-        amount,
-        to_address: owner,
-    }))
+        .add_attribute("method", "migrate")
+        .add_attribute("previous_version", stored.version)
+        .add_attribute("new_version", CONTRACT_VERSION)
+        .add_attribute("migrations_applied", applied.join(",")))
 }
 
 #[entry_point]
@@ -132,95 +2038,645 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::Entry {
             author_xyz_id,
             coordinates,
-        } => to_binary(&query_entry(deps, env, author_xyz_id, coordinates)?),
+            index,
+        } => to_binary(&query_entry(
+            deps,
+            env,
+            entry_ref(author_xyz_id, coordinates, index),
+        )?),
         QueryMsg::EntriesForXyz {
             author_xyz_id,
             start_after,
             limit,
+            include_hidden,
         } => to_binary(&query_entries_for_xyz(
             deps,
             env,
             author_xyz_id,
             start_after,
             limit,
+            include_hidden.unwrap_or(false),
         )?),
         QueryMsg::EntriesForCoordinates {
             coordinates,
             start_after,
             limit,
+            include_hidden,
         } => to_binary(&query_entries_for_coordinates(
             deps,
             env,
             coordinates,
             start_after,
             limit,
+            include_hidden.unwrap_or(false),
+        )?),
+        QueryMsg::AllEntries {
+            start_after,
+            limit,
+            order,
+            include_hidden,
+        } => to_binary(&query_all_entries(
+            deps,
+            env,
+            start_after,
+            limit,
+            order,
+            include_hidden.unwrap_or(false),
+        )?),
+        QueryMsg::Replies {
+            parent,
+            start_after,
+            limit,
+            include_hidden,
+        } => to_binary(&query_replies(
+            deps,
+            env,
+            parent,
+            start_after,
+            limit,
+            include_hidden.unwrap_or(false),
+        )?),
+        QueryMsg::TopEntries {
+            coordinates,
+            start_after,
+            limit,
+        } => to_binary(&query_top_entries(
+            deps,
+            env,
+            coordinates,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::EntriesInRegion {
+            min,
+            max,
+            start_after,
+            limit,
+            include_hidden,
+        } => to_binary(&query_entries_in_region(
+            deps,
+            env,
+            min,
+            max,
+            start_after,
+            limit,
+            include_hidden.unwrap_or(false),
+        )?),
+        QueryMsg::EntriesNear {
+            center,
+            radius,
+            start_after,
+            limit,
+            include_hidden,
+        } => to_binary(&query_entries_near(
+            deps,
+            env,
+            center,
+            radius,
+            start_after,
+            limit,
+            include_hidden.unwrap_or(false),
+        )?),
+        QueryMsg::QuoteEntryFee { coordinates } => {
+            to_binary(&query_quote_entry_fee(deps, env, coordinates)?)
+        }
+        QueryMsg::Location { coordinates } => to_binary(&query_location(deps, env, coordinates)?),
+        QueryMsg::CanPost {
+            author_xyz_id,
+            sender,
+        } => to_binary(&query_can_post(deps, env, author_xyz_id, sender)?),
+        QueryMsg::SimulateCreateEntry {
+            sender,
+            author_xyz_id,
+            text,
+            funds,
+        } => to_binary(&query_simulate_create_entry(
+            deps,
+            env,
+            sender,
+            author_xyz_id,
+            text,
+            funds,
         )?),
+        QueryMsg::Reports { start_after, limit } => {
+            to_binary(&query_reports(deps, env, start_after, limit)?)
+        }
+        QueryMsg::Moderators {} => to_binary(&query_moderators(deps, env)?),
         QueryMsg::Config {} => to_binary(&query_config(deps, env)?),
+        QueryMsg::ContentPolicy {} => to_binary(&query_content_policy(deps, env)?),
+        QueryMsg::Owner {} => to_binary(&query_owner(deps, env)?),
+        QueryMsg::PendingDistribution {} => to_binary(&query_pending_distribution(deps, env)?),
     }
 }
 
-pub fn query_entry(
-    deps: Deps,
-    _env: Env,
-    author_xyz_id: String,
-    coordinates: Coordinates,
-) -> StdResult<Entry> {
-    let entry = entries().load(deps.storage, &entries_key(author_xyz_id, coordinates))?;
+pub fn query_entry(deps: Deps, _env: Env, entry_ref: EntryRef) -> StdResult<Entry> {
+    let entry = entries().load(deps.storage, entry_ref.key())?;
     Ok(entry)
 }
 
 const DEFAULT_LIMIT: u32 = 10;
 const MAX_LIMIT: u32 = 30;
 
+// Collect a page of entries from an index range, along with an opaque cursor for the next page.
+// The cursor is the last entry's key within the index being ranged over, so passing it back as
+// an exclusive bound resumes exactly where this page ended.
+fn paginate(
+    items: impl Iterator<Item = StdResult<Pair<Entry>>>,
+    limit: usize,
+    cursor: impl Fn(&[u8], &Entry) -> Vec<u8>,
+) -> StdResult<EntriesResponse> {
+    // Fetch one extra entry to find out whether there's another page
+    let mut page = items.take(limit + 1).collect::<StdResult<Vec<_>>>()?;
+    let next_start_after = if page.len() > limit {
+        page.truncate(limit);
+        page.last()
+            .map(|(pk, entry)| Binary::from(cursor(pk.as_slice(), entry)))
+    } else {
+        None
+    };
+
+    Ok(EntriesResponse {
+        entries: page.into_iter().map(|(_, entry)| entry).collect(),
+        next_start_after,
+    })
+}
+
+// Whether an entry hidden by a moderator should be left out of a listing.
+fn is_excluded(item: &StdResult<Pair<Entry>>, include_hidden: bool) -> bool {
+    !include_hidden && matches!(item, Ok((_, entry)) if entry.hidden)
+}
+
 pub fn query_entries_for_xyz(
     deps: Deps,
     _env: Env,
     author_xyz_id: String,
-    start_after: Option<String>,
+    start_after: Option<Binary>,
     limit: Option<u32>,
+    include_hidden: bool,
 ) -> StdResult<EntriesResponse> {
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
     let start = start_after.map(Bound::exclusive);
 
-    let entries_for_xyz: StdResult<Vec<_>> = entries()
+    // Within an xyz's prefix, the index is ordered by the entries' joined primary keys
+    let items = entries()
         .idx
         .author_xyz_id
         .prefix(author_xyz_id)
         .range(deps.storage, start, None, Order::Ascending)
-        .take(limit)
-        .map(|item| item.map(|(_, entry)| entry))
-        .collect();
+        .filter(|item| !is_excluded(item, include_hidden));
 
-    Ok(EntriesResponse {
-        entries: entries_for_xyz?,
-    })
+    paginate(items, limit, |pk, _| pk.to_vec())
 }
 
 pub fn query_entries_for_coordinates(
     deps: Deps,
     _env: Env,
     coordinates: Coordinates,
-    start_after: Option<String>,
+    start_after: Option<Binary>,
     limit: Option<u32>,
+    include_hidden: bool,
 ) -> StdResult<EntriesResponse> {
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
     let start = start_after.map(Bound::exclusive);
 
-    let entries_for_coordinates: StdResult<Vec<_>> = entries()
+    // Within a location's prefix, the index is ordered by the entries' pin ranks, so that entries
+    // the location's host has pinned come first, and then by their joined primary keys. Replies
+    // are left out of a location's guestbook, since they're listed in their parent's thread, and
+    // so are entries the location's host has hidden.
+    let items = entries()
         .idx
         .coordinates
-        .prefix(coordinates.to_bytes())
+        .sub_prefix(coordinates.to_bytes())
         .range(deps.storage, start, None, Order::Ascending)
-        .take(limit)
-        .map(|item| item.map(|(_, entry)| entry))
-        .collect();
+        .filter(|item| !matches!(item, Ok((_, entry)) if entry.parent.is_some()))
+        .filter(|item| {
+            include_hidden
+                || !matches!(item, Ok((_, entry)) if entry.hidden || entry.hidden_by_host)
+        });
 
-    Ok(EntriesResponse {
-        entries: entries_for_coordinates?,
+    paginate(items, limit, |pk, entry| {
+        (pin_rank(entry), pk.to_vec()).joined_key()
+    })
+}
+
+pub fn query_all_entries(
+    deps: Deps,
+    _env: Env,
+    start_after: Option<Binary>,
+    limit: Option<u32>,
+    order: Option<OrderBy>,
+    include_hidden: bool,
+) -> StdResult<EntriesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let order: Order = order.unwrap_or(OrderBy::Ascending).into();
+    let start = start_after.map(Bound::exclusive);
+    let (min, max) = match order {
+        Order::Ascending => (start, None),
+        Order::Descending => (None, start),
+    };
+
+    // The sequence index is ordered by the entries' big-endian sequence numbers
+    let items = entries()
+        .idx
+        .seq
+        .range(deps.storage, min, max, order)
+        .filter(|item| !is_excluded(item, include_hidden));
+
+    paginate(items, limit, |_, entry| entry.seq.to_be_bytes().to_vec())
+}
+
+pub fn query_replies(
+    deps: Deps,
+    _env: Env,
+    parent: EntryRef,
+    start_after: Option<Binary>,
+    limit: Option<u32>,
+    include_hidden: bool,
+) -> StdResult<EntriesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    // Within a parent's prefix, the index is ordered by the replies' joined primary keys
+    let items = entries()
+        .idx
+        .replies
+        .prefix(parent.key().joined_key())
+        .range(deps.storage, start, None, Order::Ascending)
+        .filter(|item| !is_excluded(item, include_hidden));
+
+    paginate(items, limit, |pk, _| pk.to_vec())
+}
+
+pub fn query_top_entries(
+    deps: Deps,
+    _env: Env,
+    coordinates: Option<Coordinates>,
+    start_after: Option<Binary>,
+    limit: Option<u32>,
+) -> StdResult<EntriesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let end = start_after.map(Bound::exclusive);
+
+    // Both score indexes are ordered by score and then primary key, and are ranged over from the
+    // highest score down. Within a location's prefix, the location score index ranks the entries
+    // pinned by the location's host above the rest, while pins don't affect the overall ranking.
+    // Entries hidden by a moderator are left out, as are entries hidden by a location's host and
+    // replies, which belong to their parent's thread, from the location's own ranking.
+    match coordinates {
+        Some(coordinates) => {
+            let items = entries()
+                .idx
+                .location_score
+                .sub_prefix(coordinates.to_bytes())
+                .range(deps.storage, None, end, Order::Descending)
+                .filter(|item| !matches!(item, Ok((_, entry)) if entry.parent.is_some()))
+                .filter(
+                    |item| !matches!(item, Ok((_, entry)) if entry.hidden || entry.hidden_by_host),
+                );
+            paginate(items, limit, |pk, entry| {
+                (location_score_rank(entry), pk.to_vec()).joined_key()
+            })
+        }
+        None => {
+            let items = entries()
+                .idx
+                .score
+                .sub_prefix(())
+                .range(deps.storage, None, end, Order::Descending)
+                .filter(|item| !is_excluded(item, false));
+            paginate(items, limit, |pk, entry| {
+                (U32Key::new(entry.score), pk.to_vec()).joined_key()
+            })
+        }
+    }
+}
+
+// The largest number of region buckets that a single region query may scan.
+const MAX_REGION_BUCKETS: i128 = 512;
+
+// Split a region cursor into the region bucket and primary key of the last entry returned.
+fn parse_region_cursor(cursor: &[u8]) -> StdResult<(RegionBucket, Vec<u8>)> {
+    if cursor.len() < 24 {
+        return Err(StdError::generic_err("Invalid cursor"));
+    }
+    let axis = |i: usize| {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&cursor[i * 8..(i + 1) * 8]);
+        i64::from_be_bytes(bytes)
+    };
+    Ok(((axis(0), axis(1), axis(2)), cursor[24..].to_vec()))
+}
+
+// Collect a page of entries whose coordinates lie within an axis-aligned box and satisfy the
+// given predicate, scanning only the region buckets that overlap the box. The cursor is the last
+// entry's region bucket followed by its primary key, so paging resumes within the right bucket.
+fn query_entries_in_box(
+    deps: Deps,
+    min: Point,
+    max: Point,
+    start_after: Option<Binary>,
+    limit: Option<u32>,
+    include_hidden: bool,
+    include: impl Fn(Point) -> bool,
+) -> StdResult<EntriesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    if min.0 > max.0 || min.1 > max.1 || min.2 > max.2 {
+        return Err(StdError::generic_err("Invalid region"));
+    }
+
+    // Check that the region doesn't span too many buckets to scan
+    let (min_bucket, max_bucket) = (region_bucket(min), region_bucket(max));
+    let span = |min: i64, max: i64| i128::from(max) - i128::from(min) + 1;
+    let num_buckets = span(min_bucket.0, max_bucket.0)
+        .checked_mul(span(min_bucket.1, max_bucket.1))
+        .and_then(|n| n.checked_mul(span(min_bucket.2, max_bucket.2)));
+    match num_buckets {
+        Some(num_buckets) if num_buckets <= MAX_REGION_BUCKETS => {}
+        _ => return Err(StdError::generic_err("Region too large")),
+    }
+
+    let (resume_bucket, resume_pk) = match start_after {
+        Some(cursor) => {
+            let (bucket, pk) = parse_region_cursor(&cursor)?;
+            (Some(bucket), Some(pk))
+        }
+        None => (None, None),
+    };
+
+    // Buckets are scanned in the same order as their cursors compare
+    let mut buckets = vec![];
+    for x in min_bucket.0..=max_bucket.0 {
+        for y in min_bucket.1..=max_bucket.1 {
+            for z in min_bucket.2..=max_bucket.2 {
+                buckets.push((x, y, z));
+            }
+        }
+    }
+
+    let storage = deps.storage;
+    let items = buckets
+        .into_iter()
+        .skip_while(
+            |bucket| matches!(resume_bucket, Some(resume_bucket) if *bucket < resume_bucket),
+        )
+        .flat_map(move |bucket| {
+            let start = match (resume_bucket, &resume_pk) {
+                (Some(resume_bucket), Some(pk)) if resume_bucket == bucket => {
+                    Some(Bound::exclusive(pk.clone()))
+                }
+                _ => None,
+            };
+            entries()
+                .idx
+                .region
+                .prefix(region_bucket_to_bytes(bucket))
+                .range(storage, start, None, Order::Ascending)
+        })
+        // Like a location's guestbook, a region leaves out replies, which are listed in their
+        // parent's thread, and entries hidden by a moderator or by their location's host
+        .filter(|item| !matches!(item, Ok((_, entry)) if entry.parent.is_some()))
+        .filter(|item| {
+            include_hidden
+                || !matches!(item, Ok((_, entry)) if entry.hidden || entry.hidden_by_host)
+        })
+        .filter(|item| match item {
+            Ok((_, entry)) => {
+                let point = coordinates_to_point(&entry.coordinates);
+                (min.0..=max.0).contains(&point.0)
+                    && (min.1..=max.1).contains(&point.1)
+                    && (min.2..=max.2).contains(&point.2)
+                    && include(point)
+            }
+            Err(_) => true,
+        });
+
+    paginate(items, limit, |pk, entry| {
+        let bucket = region_bucket(coordinates_to_point(&entry.coordinates));
+        [region_bucket_to_bytes(bucket), pk.to_vec()].concat()
+    })
+}
+
+pub fn query_entries_in_region(
+    deps: Deps,
+    _env: Env,
+    min: Coordinates,
+    max: Coordinates,
+    start_after: Option<Binary>,
+    limit: Option<u32>,
+    include_hidden: bool,
+) -> StdResult<EntriesResponse> {
+    query_entries_in_box(
+        deps,
+        coordinates_to_point(&min),
+        coordinates_to_point(&max),
+        start_after,
+        limit,
+        include_hidden,
+        |_| true,
+    )
+}
+
+pub fn query_entries_near(
+    deps: Deps,
+    _env: Env,
+    center: Coordinates,
+    radius: u32,
+    start_after: Option<Binary>,
+    limit: Option<u32>,
+    include_hidden: bool,
+) -> StdResult<EntriesResponse> {
+    let center = coordinates_to_point(&center);
+    let radius = i64::from(radius);
+
+    // Search the box bounding the sphere, keeping only entries within the radius. The box is
+    // clamped to the range of coordinates, which no entry lies beyond.
+    query_entries_in_box(
+        deps,
+        (
+            center.0.saturating_sub(radius),
+            center.1.saturating_sub(radius),
+            center.2.saturating_sub(radius),
+        ),
+        (
+            center.0.saturating_add(radius),
+            center.1.saturating_add(radius),
+            center.2.saturating_add(radius),
+        ),
+        start_after,
+        limit,
+        include_hidden,
+        |point| {
+            let square = |a: i64, b: i64| (i128::from(a) - i128::from(b)).pow(2);
+            square(point.0, center.0) + square(point.1, center.1) + square(point.2, center.2)
+                <= i128::from(radius).pow(2)
+        },
+    )
+}
+
+pub fn query_quote_entry_fee(
+    deps: Deps,
+    _env: Env,
+    coordinates: Coordinates,
+) -> StdResult<EntryFeeQuoteResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let (entry_fees, cw20_entry_fees) = quote_entry_fees(deps, &config, coordinates)?;
+    Ok(EntryFeeQuoteResponse {
+        entry_fees,
+        cw20_entry_fees,
+    })
+}
+
+pub fn query_location(
+    deps: Deps,
+    _env: Env,
+    coordinates: Coordinates,
+) -> StdResult<LocationResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let location = active_location(deps, &config, coordinates)?.map(|(location, _)| location);
+    let entry_count = LOCATION_ENTRY_COUNTS
+        .may_load(deps.storage, &coordinates.to_bytes())?
+        .unwrap_or_default();
+    Ok(LocationResponse {
+        coordinates,
+        host_xyz_id: location
+            .as_ref()
+            .map(|location| location.host_xyz_id.clone()),
+        surcharge_bps: location.map_or(0, |location| location.surcharge_bps),
+        entry_count,
+    })
+}
+
+pub fn query_can_post(
+    deps: Deps,
+    env: Env,
+    author_xyz_id: String,
+    sender: Option<String>,
+) -> StdResult<CanPostResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let xyz = query_xyz(deps, &config, &author_xyz_id)?;
+    let coordinates = xyz.extension.coordinates;
+
+    let sender_approved = match sender {
+        Some(sender) => {
+            let sender = deps.api.addr_validate(&sender)?;
+            Some(is_xyz_poster(deps, &env, &config, &xyz, &sender)?)
+        }
+        None => None,
+    };
+    let arrived = xyz.extension.has_arrived(env.block.time);
+    let stay_complete_at = stay_complete_at(&config, &xyz);
+    let stay_complete = env.block.time >= stay_complete_at;
+    let (entry_fees, cw20_entry_fees) = quote_entry_fees(deps, &config, coordinates)?;
+    let history = posting_history(deps.storage, &author_xyz_id, coordinates)?;
+    let entry_cap_reached = history.signature_count >= config.max_entries_per_location;
+    let cooldown_ends_at =
+        cooldown_ends_at(&config, &history).filter(|cooldown_end| env.block.time < *cooldown_end);
+
+    Ok(CanPostResponse {
+        can_post: sender_approved != Some(false)
+            && arrived
+            && stay_complete
+            && !entry_cap_reached
+            && cooldown_ends_at.is_none(),
+        owner: xyz.owner.to_string(),
+        sender_approved,
+        coordinates,
+        arrived,
+        stay_complete_at,
+        stay_complete,
+        entry_fees,
+        cw20_entry_fees,
+        entry_count: history.signature_count,
+        entry_cap_reached,
+        cooldown_ends_at,
     })
 }
 
+pub fn query_simulate_create_entry(
+    deps: Deps,
+    env: Env,
+    sender: String,
+    author_xyz_id: String,
+    text: String,
+    funds: Vec<Coin>,
+) -> StdResult<SimulateCreateEntryResponse> {
+    let sender = deps.api.addr_validate(&sender)?;
+    let entry = NewEntry {
+        author_xyz_id,
+        text,
+        parent: None,
+        late: false,
+    };
+    let violations = match check_new_entry(deps, &env, &sender, &entry, Payment::Native(funds))? {
+        Ok(_) => vec![],
+        Err(violations) => violations.iter().map(violation_of).collect(),
+    };
+    Ok(SimulateCreateEntryResponse { violations })
+}
+
+pub fn query_reports(
+    deps: Deps,
+    _env: Env,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<ReportsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive_int);
+
+    // Open reports are listed oldest first
+    let reports = reports()
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, report)| report))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(ReportsResponse { reports })
+}
+
+pub fn query_moderators(deps: Deps, _env: Env) -> StdResult<ModeratorsResponse> {
+    let moderators = MODERATORS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .map(String::from_utf8)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(StdError::from)?;
+    Ok(ModeratorsResponse { moderators })
+}
+
 pub fn query_config(deps: Deps, _env: Env) -> StdResult<Config> {
     let config = CONFIG.load(deps.storage)?;
     Ok(config)
 }
+
+pub fn query_content_policy(deps: Deps, _env: Env) -> StdResult<ContentPolicy> {
+    Ok(CONTENT_POLICY.may_load(deps.storage)?.unwrap_or_default())
+}
+
+pub fn query_owner(deps: Deps, _env: Env) -> StdResult<OwnerResponse> {
+    Ok(OwnerResponse {
+        owner: OWNER.may_load(deps.storage)?,
+        pending_owner: PENDING_OWNER.may_load(deps.storage)?,
+    })
+}
+
+pub fn query_pending_distribution(deps: Deps, env: Env) -> StdResult<PendingDistributionResponse> {
+    let payees = PAYEES.may_load(deps.storage)?.unwrap_or_default();
+    let balance = deps
+        .querier
+        .query_all_balances(env.contract.address.clone())?;
+    let cw20_balance = query_cw20_balance(deps, &env)?;
+    let split = split_balance(&balance, &payees);
+    let cw20_split = split_cw20_balance(&cw20_balance, &payees);
+
+    Ok(PendingDistributionResponse {
+        payments: payees
+            .into_iter()
+            .zip(split)
+            .zip(cw20_split)
+            .map(|((payee, amount), cw20_amount)| PendingPayment {
+                payee,
+                amount,
+                cw20_amount,
+            })
+            .collect(),
+    })
+}
@@ -2,13 +2,29 @@ use std::collections::HashMap;
 
 use collectxyz::nft::{Coordinates, XyzExtension, XyzTokenInfo};
 use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-use cosmwasm_std::{from_binary, Addr, BankMsg, Coin, DepsMut, Response, StdError, Timestamp};
+use cosmwasm_std::{
+    from_binary, to_binary, Addr, BankMsg, Binary, Coin, Deps, DepsMut, Response, StdError,
+    Timestamp, Uint128, WasmMsg,
+};
+use cw2::{get_contract_version, set_contract_version};
+use cw20::{Cw20Coin, Cw20CoinVerified, Cw20ExecuteMsg, Cw20ReceiveMsg};
+use cw721::{Approval as Cw721Approval, Expiration};
+use cw721_base::state::Approval;
 
 use crate::contract;
 use crate::error::ContractError;
+use crate::migrations::{entries_v0_1, ConfigV0_1, EntryV0_1, CONFIG_V0_1};
 use crate::mock_querier::mock_dependencies_xyz;
-use crate::msg::{EntriesResponse, ExecuteMsg, InstantiateMsg, QueryMsg};
-use crate::state::{Config, Entry};
+use crate::msg::{
+    CanPostResponse, EntriesResponse, EntryFeeQuoteResponse, ExecuteMsg, InstantiateMsg,
+    LocationResponse, MigrateMsg, ModeratorsResponse, OrderBy, OwnerResponse, PayeeMsg,
+    PendingDistributionResponse, PendingPayment, QueryMsg, ReceiveMsg, ReportsResponse,
+    SimulateCreateEntryResponse, UpdateConfigMsg, Violation,
+};
+use crate::state::{
+    CharacterCategory, Config, ContentPolicy, Entry, EntryRef, LimitMode, Payee, PostingHistory,
+    PricingMode, ReactionCount, Report, POSTING_HISTORY,
+};
 
 const OWNER: &str = "owner";
 const ADDR1: &str = "addr1";
@@ -52,7 +68,19 @@ fn get_initial_xyz_balances() -> HashMap<String, XyzTokenInfo> {
 fn get_initial_config() -> Config {
     Config {
         character_limit: 240,
-        entry_fee: Coin::new(1000, "uluna"),
+        limit_mode: LimitMode::Bytes,
+        entry_fees: vec![Coin::new(1000, "uluna"), Coin::new(1500, "uusd")],
+        cw20_entry_fees: vec![Cw20CoinVerified {
+            address: Addr::unchecked("token-contract"),
+            amount: Uint128::new(1000),
+        }],
+        tip_fee_bps: 500,
+        pricing: PricingMode::Flat,
+        allowed_reactions: vec!["like".to_string(), "🔥".to_string()],
+        max_entries_per_location: 1,
+        entry_cooldown_seconds: 0,
+        min_stay_seconds: 0,
+        late_signature_grace_seconds: 0,
         xyz_nft_contract: Addr::unchecked("xyz-nft-contract"),
     }
 }
@@ -168,6 +196,7 @@ fn create_entry() {
             .add_attribute("xyz_coordinates_x", "1")
             .add_attribute("xyz_coordinates_y", "1")
             .add_attribute("xyz_coordinates_z", "1")
+            .add_attribute("entry_index", "0")
     );
 
     // check that the entry was created
@@ -178,7 +207,8 @@ fn create_entry() {
             mock_env(),
             QueryMsg::Entry {
                 author_xyz_id: "xyz #1".to_string(),
-                coordinates: entry_coords.clone(),
+                coordinates: entry_coords,
+                index: None,
             },
         )
         .unwrap(),
@@ -189,7 +219,22 @@ fn create_entry() {
         Entry {
             author_xyz_id: "xyz #1".to_string(),
             text: entry_text.to_string(),
-            coordinates: entry_coords.clone()
+            coordinates: entry_coords,
+            index: 0,
+            seq: 1,
+            created_at: mock_env().block.time,
+            block_height: mock_env().block.height,
+            author_address: Addr::unchecked(ADDR1),
+            edit_count: 0,
+            last_edited: None,
+            tips: vec![],
+            pinned: false,
+            hidden_by_host: false,
+            hidden: false,
+            parent: None,
+            reactions: vec![],
+            score: 0,
+            late: false,
         }
     );
 
@@ -208,7 +253,7 @@ fn create_entry() {
 
     // can create an entry with same xyz at a different location
     let mut new_xyz_balances = xyz_balances.clone();
-    let moved_xyz = new_xyz_balances.get_mut(&"xyz #1".to_string()).unwrap();
+    let moved_xyz = new_xyz_balances.get_mut("xyz #1").unwrap();
     moved_xyz.extension.coordinates = Coordinates { x: 3, y: 3, z: 3 };
     deps.querier.update_xyz_balances(new_xyz_balances);
     let res = contract::execute(
@@ -229,6 +274,2199 @@ fn create_entry() {
             .add_attribute("xyz_coordinates_x", "3")
             .add_attribute("xyz_coordinates_y", "3")
             .add_attribute("xyz_coordinates_z", "3")
+            .add_attribute("entry_index", "0")
+    );
+}
+
+#[test]
+fn character_limit_modes() {
+    let mut deps = mock_dependencies_xyz(get_initial_xyz_balances(), &[]);
+    setup_contract(deps.as_mut());
+
+    // a family emoji is 5 chars and 18 bytes joined into 1 grapheme, and a party popper is
+    // 1 char and 4 bytes
+    let entry_text = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}\u{1F389}";
+    for (limit_mode, length) in [
+        (LimitMode::Bytes, Some(22)),
+        (LimitMode::Chars, Some(6)),
+        (LimitMode::Graphemes, None),
+    ] {
+        let _ = contract::execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &[]),
+            ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+                character_limit: Some(4),
+                limit_mode: Some(limit_mode),
+                ..UpdateConfigMsg::default()
+            }),
+        )
+        .unwrap();
+        let res = contract::execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(ADDR1, &[Coin::new(1000, "uluna")]),
+            ExecuteMsg::CreateEntry {
+                author_xyz_id: "xyz #1".to_string(),
+                text: entry_text.to_string(),
+            },
+        );
+        match length {
+            Some(length) => assert_eq!(
+                res.unwrap_err(),
+                ContractError::CharacterLimitExceeded { length, limit: 4 }
+            ),
+            None => assert!(res.is_ok()),
+        }
+    }
+}
+
+#[test]
+fn multiple_entries_per_location() {
+    let mut deps = mock_dependencies_xyz(get_initial_xyz_balances(), &[]);
+    setup_contract(deps.as_mut());
+
+    // the cap can't be zero
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+            max_entries_per_location: Some(0),
+            ..UpdateConfigMsg::default()
+        }),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidMaxEntries {});
+
+    // allow two entries per location, an hour apart
+    let _ = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+            max_entries_per_location: Some(2),
+            entry_cooldown_seconds: Some(3600),
+            ..UpdateConfigMsg::default()
+        }),
+    )
+    .unwrap();
+    let create_entry = |deps: DepsMut, env, text: &str| {
+        contract::execute(
+            deps,
+            env,
+            mock_info(ADDR1, &[Coin::new(1000, "uluna")]),
+            ExecuteMsg::CreateEntry {
+                author_xyz_id: "xyz #1".to_string(),
+                text: text.to_string(),
+            },
+        )
+    };
+    let _ = create_entry(deps.as_mut(), mock_env(), "first visit").unwrap();
+
+    // can't post again at the same location during the cooldown
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(3599);
+    let err = create_entry(deps.as_mut(), env.clone(), "second visit").unwrap_err();
+    assert_eq!(err, ContractError::EntryCooldownActive {});
+
+    // the next entry after the cooldown gets the next index
+    env.block.time = env.block.time.plus_seconds(1);
+    let res = create_entry(deps.as_mut(), env.clone(), "second visit").unwrap();
+    assert_eq!(res.attributes[5].key, "entry_index");
+    assert_eq!(res.attributes[5].value, "1");
+    let entry = from_binary::<Entry>(
+        &contract::query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Entry {
+                author_xyz_id: "xyz #1".to_string(),
+                coordinates: Coordinates { x: 1, y: 1, z: 1 },
+                index: Some(1),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(entry.text, "second visit");
+    assert_eq!(entry.created_at, env.block.time);
+
+    // the first entry is untouched
+    let entry = from_binary::<Entry>(
+        &contract::query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Entry {
+                author_xyz_id: "xyz #1".to_string(),
+                coordinates: Coordinates { x: 1, y: 1, z: 1 },
+                index: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(entry.text, "first visit");
+
+    // can't go over the cap, however long the xyz waits
+    env.block.time = env.block.time.plus_seconds(86400);
+    let err = create_entry(deps.as_mut(), env.clone(), "third visit").unwrap_err();
+    assert_eq!(err, ContractError::EntryAlreadyExists {});
+
+    // deleting an entry frees up its place under the cap, but its index isn't reused
+    let delete_entry = |deps: DepsMut, env, index| {
+        contract::execute(
+            deps,
+            env,
+            mock_info(ADDR1, &[]),
+            ExecuteMsg::DeleteEntry {
+                author_xyz_id: "xyz #1".to_string(),
+                coordinates: Coordinates { x: 1, y: 1, z: 1 },
+                index: Some(index),
+            },
+        )
+        .unwrap()
+    };
+    delete_entry(deps.as_mut(), env.clone(), 1);
+    let res = create_entry(deps.as_mut(), env.clone(), "third visit").unwrap();
+    assert_eq!(res.attributes[5].value, "2");
+
+    // deleting the latest entry doesn't end the cooldown early
+    delete_entry(deps.as_mut(), env.clone(), 2);
+    env.block.time = env.block.time.plus_seconds(1);
+    let err = create_entry(deps.as_mut(), env.clone(), "fourth visit").unwrap_err();
+    assert_eq!(err, ContractError::EntryCooldownActive {});
+    env.block.time = env.block.time.plus_seconds(3599);
+    let res = create_entry(deps.as_mut(), env, "fourth visit").unwrap();
+    assert_eq!(res.attributes[5].value, "3");
+}
+
+#[test]
+fn minimum_stay() {
+    let mut deps = mock_dependencies_xyz(get_initial_xyz_balances(), &[]);
+    setup_contract(deps.as_mut());
+    let _ = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+            min_stay_seconds: Some(3600),
+            ..UpdateConfigMsg::default()
+        }),
+    )
+    .unwrap();
+    let can_post = |deps: Deps, env, sender: Option<&str>| {
+        from_binary::<CanPostResponse>(
+            &contract::query(
+                deps,
+                env,
+                QueryMsg::CanPost {
+                    author_xyz_id: "xyz #1".to_string(),
+                    sender: sender.map(str::to_string),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    };
+    let create_entry = |deps: DepsMut, env| {
+        contract::execute(
+            deps,
+            env,
+            mock_info(ADDR1, &[Coin::new(1000, "uluna")]),
+            ExecuteMsg::CreateEntry {
+                author_xyz_id: "xyz #1".to_string(),
+                text: "hello".to_string(),
+            },
+        )
+    };
+
+    // xyz #1 arrived a minute ago, so it can't post until it has stayed an hour
+    let arrival = mock_env().block.time.minus_seconds(60);
+    let mut xyz_balances = get_initial_xyz_balances();
+    xyz_balances.get_mut("xyz #1").unwrap().extension.arrival = arrival;
+    deps.querier.update_xyz_balances(xyz_balances);
+    let err = create_entry(deps.as_mut(), mock_env()).unwrap_err();
+    assert_eq!(err, ContractError::MinimumStayNotMet {});
+    assert_eq!(
+        can_post(deps.as_ref(), mock_env(), Some(ADDR1)),
+        CanPostResponse {
+            can_post: false,
+            owner: ADDR1.to_string(),
+            sender_approved: Some(true),
+            coordinates: Coordinates { x: 1, y: 1, z: 1 },
+            arrived: true,
+            stay_complete_at: arrival.plus_seconds(3600),
+            stay_complete: false,
+            entry_fees: vec![Coin::new(1000, "uluna"), Coin::new(1500, "uusd")],
+            cw20_entry_fees: vec![Cw20CoinVerified {
+                address: Addr::unchecked("token-contract"),
+                amount: Uint128::new(1000),
+            }],
+            entry_count: 0,
+            entry_cap_reached: false,
+            cooldown_ends_at: None,
+        }
+    );
+
+    // once the stay is complete, the owner can post but other addresses can't
+    let mut env = mock_env();
+    env.block.time = arrival.plus_seconds(3600);
+    let res = can_post(deps.as_ref(), env.clone(), Some(ADDR1));
+    assert!(res.stay_complete);
+    assert!(res.can_post);
+    let res = can_post(deps.as_ref(), env.clone(), Some(ADDR2));
+    assert_eq!(res.sender_approved, Some(false));
+    assert!(!res.can_post);
+    let res = can_post(deps.as_ref(), env.clone(), None);
+    assert_eq!(res.sender_approved, None);
+    assert!(res.can_post);
+    let _ = create_entry(deps.as_mut(), env.clone()).unwrap();
+
+    // the xyz can't post again at the same location
+    let res = can_post(deps.as_ref(), env.clone(), Some(ADDR1));
+    assert_eq!(res.entry_count, 1);
+    assert!(res.entry_cap_reached);
+    assert!(!res.can_post);
+
+    // an xyz that's still relocating can't post
+    let mut xyz_balances = get_initial_xyz_balances();
+    let xyz = xyz_balances.get_mut("xyz #1").unwrap();
+    xyz.extension.coordinates = Coordinates { x: 3, y: 3, z: 3 };
+    xyz.extension.arrival = env.block.time.plus_seconds(60);
+    deps.querier.update_xyz_balances(xyz_balances);
+    let res = can_post(deps.as_ref(), env.clone(), Some(ADDR1));
+    assert!(!res.arrived);
+    assert!(!res.stay_complete);
+    assert_eq!(res.entry_count, 0);
+    assert!(!res.can_post);
+    let err = create_entry(deps.as_mut(), env).unwrap_err();
+    assert_eq!(err, ContractError::RelocationInProgress {});
+}
+
+#[test]
+fn simulate_create_entry() {
+    let mut deps = mock_dependencies_xyz(get_initial_xyz_balances(), &[]);
+    setup_contract(deps.as_mut());
+    let simulate = |deps: Deps, sender: &str, text: &str, funds: Vec<Coin>| {
+        from_binary::<SimulateCreateEntryResponse>(
+            &contract::query(
+                deps,
+                mock_env(),
+                QueryMsg::SimulateCreateEntry {
+                    sender: sender.to_string(),
+                    author_xyz_id: "xyz #1".to_string(),
+                    text: text.to_string(),
+                    funds,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+        .violations
+    };
+
+    // every violation is reported at once
+    let mut xyz_balances = get_initial_xyz_balances();
+    xyz_balances.get_mut("xyz #1").unwrap().extension.arrival =
+        mock_env().block.time.plus_seconds(60);
+    deps.querier.update_xyz_balances(xyz_balances);
+    assert_eq!(
+        simulate(deps.as_ref(), ADDR2, &"a".repeat(241), vec![]),
+        vec![
+            Violation::CharacterLimitExceeded {
+                length: 241,
+                limit: 240
+            },
+            Violation::Unauthorized {},
+            Violation::RelocationInProgress {},
+            Violation::InsufficientFunds {},
+        ]
+    );
+    assert_eq!(
+        simulate(deps.as_ref(), ADDR1, "hello", vec![Coin::new(1000, "ukrw")]),
+        vec![Violation::RelocationInProgress {}, Violation::WrongDenom {}]
+    );
+
+    // an xyz that can't be looked up doesn't stop the other checks
+    let res = from_binary::<SimulateCreateEntryResponse>(
+        &contract::query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::SimulateCreateEntry {
+                sender: ADDR1.to_string(),
+                author_xyz_id: "xyz #123456".to_string(),
+                text: "a".repeat(241),
+                funds: vec![Coin::new(1000, "ukrw")],
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.violations,
+        vec![
+            Violation::CharacterLimitExceeded {
+                length: 241,
+                limit: 240
+            },
+            Violation::XyzNotFound {},
+            Violation::WrongDenom {},
+        ]
+    );
+
+    // a valid entry has no violations, and simulating it doesn't create it
+    deps.querier.update_xyz_balances(get_initial_xyz_balances());
+    assert!(simulate(
+        deps.as_ref(),
+        ADDR1,
+        "hello",
+        vec![Coin::new(1000, "uluna")]
+    )
+    .is_empty());
+    let location = from_binary::<LocationResponse>(
+        &contract::query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Location {
+                coordinates: Coordinates { x: 1, y: 1, z: 1 },
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(location.entry_count, 0);
+
+    // the simulation agrees with creating the entry
+    let _ = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR1, &[Coin::new(1000, "uluna")]),
+        ExecuteMsg::CreateEntry {
+            author_xyz_id: "xyz #1".to_string(),
+            text: "hello".to_string(),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        simulate(
+            deps.as_ref(),
+            ADDR1,
+            "hello",
+            vec![Coin::new(1000, "uluna")]
+        ),
+        vec![Violation::EntryCapReached {}]
+    );
+}
+
+#[test]
+fn late_signatures() {
+    let mut deps = mock_dependencies_xyz(get_initial_xyz_balances(), &[]);
+    setup_contract(deps.as_mut());
+    let create_late_entry = |deps: DepsMut, env, author_xyz_id: &str, sender: &str| {
+        contract::execute(
+            deps,
+            env,
+            mock_info(sender, &[Coin::new(1000, "uluna")]),
+            ExecuteMsg::CreateLateEntry {
+                author_xyz_id: author_xyz_id.to_string(),
+                text: "sorry I missed you".to_string(),
+            },
+        )
+    };
+
+    // xyz #1 is relocating from (1, 1, 1) to (3, 3, 3), which takes two minutes, so it started
+    // relocating a minute ago
+    let arrival = mock_env().block.time.plus_seconds(60);
+    let mut xyz_balances = get_initial_xyz_balances();
+    let xyz = xyz_balances.get_mut("xyz #1").unwrap();
+    xyz.extension.prev_coordinates = Some(Coordinates { x: 1, y: 1, z: 1 });
+    xyz.extension.coordinates = Coordinates { x: 3, y: 3, z: 3 };
+    xyz.extension.arrival = arrival;
+    deps.querier.update_xyz_balances(xyz_balances.clone());
+
+    // late signatures are disabled by default
+    let err = create_late_entry(deps.as_mut(), mock_env(), "xyz #1", ADDR1).unwrap_err();
+    assert_eq!(err, ContractError::LateSignatureUnavailable {});
+    let _ = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+            late_signature_grace_seconds: Some(3600),
+            ..UpdateConfigMsg::default()
+        }),
+    )
+    .unwrap();
+
+    // an xyz that hasn't moved has no previous location to sign
+    let err = create_late_entry(deps.as_mut(), mock_env(), "xyz #2", ADDR2).unwrap_err();
+    assert_eq!(err, ContractError::LateSignatureUnavailable {});
+
+    // only the xyz's owner or approved addresses can sign for it
+    let err = create_late_entry(deps.as_mut(), mock_env(), "xyz #1", ADDR2).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // while relocating, xyz #1 can sign the guestbook at its previous location
+    let res = create_late_entry(deps.as_mut(), mock_env(), "xyz #1", ADDR1).unwrap();
+    assert_eq!(
+        res,
+        Response::default()
+            .add_attribute("action", "entry_created")
+            .add_attribute("xyz_id", "xyz #1")
+            .add_attribute("xyz_coordinates_x", "1")
+            .add_attribute("xyz_coordinates_y", "1")
+            .add_attribute("xyz_coordinates_z", "1")
+            .add_attribute("entry_index", "0")
+            .add_attribute("late", "true")
+    );
+    let entry = from_binary::<Entry>(
+        &contract::query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Entry {
+                author_xyz_id: "xyz #1".to_string(),
+                coordinates: Coordinates { x: 1, y: 1, z: 1 },
+                index: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert!(entry.late);
+
+    // late signatures count toward the cap on entries at the previous location
+    let err = create_late_entry(deps.as_mut(), mock_env(), "xyz #1", ADDR1).unwrap_err();
+    assert_eq!(err, ContractError::EntryAlreadyExists {});
+
+    // once it arrives, the xyz can no longer sign its previous location, even though the window
+    // since it started relocating from (4, 4, 4) is still open
+    let xyz = xyz_balances.get_mut("xyz #1").unwrap();
+    xyz.extension.prev_coordinates = Some(Coordinates { x: 4, y: 4, z: 4 });
+    deps.querier.update_xyz_balances(xyz_balances);
+    let mut env = mock_env();
+    env.block.time = arrival;
+    let err = create_late_entry(deps.as_mut(), env.clone(), "xyz #1", ADDR1).unwrap_err();
+    assert_eq!(err, ContractError::LateSignatureUnavailable {});
+    env.block.time = arrival.minus_seconds(1);
+    let _ = create_late_entry(deps.as_mut(), env, "xyz #1", ADDR1).unwrap();
+
+    // the window closes even if the xyz is still on a long relocation, here one that takes over
+    // eight hours, started nearly three hours ago
+    let mut xyz_balances = get_initial_xyz_balances();
+    let xyz = xyz_balances.get_mut("xyz #2").unwrap();
+    xyz.extension.prev_coordinates = Some(Coordinates { x: 2, y: 2, z: 2 });
+    xyz.extension.coordinates = Coordinates {
+        x: 1000,
+        y: 1000,
+        z: 1000,
+    };
+    xyz.extension.arrival = mock_env().block.time.plus_seconds(20000);
+    deps.querier.update_xyz_balances(xyz_balances);
+    let err = create_late_entry(deps.as_mut(), mock_env(), "xyz #2", ADDR2).unwrap_err();
+    assert_eq!(err, ContractError::LateSignatureUnavailable {});
+}
+
+#[test]
+fn approved_posters() {
+    let mut xyz_balances = get_initial_xyz_balances();
+    let mut deps = mock_dependencies_xyz(xyz_balances.clone(), &[]);
+    setup_contract(deps.as_mut());
+    let create_entry = |deps: DepsMut, sender: &str, author_xyz_id: &str| {
+        contract::execute(
+            deps,
+            mock_env(),
+            mock_info(sender, &[Coin::new(1000, "uluna")]),
+            ExecuteMsg::CreateEntry {
+                author_xyz_id: author_xyz_id.to_string(),
+                text: format!("{} was here", author_xyz_id),
+            },
+        )
+    };
+
+    // addresses without an approval can't post for an xyz
+    let err = create_entry(deps.as_mut(), "bot", "xyz #1").unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // addresses with an unexpired approval for the xyz can post for it
+    xyz_balances.get_mut("xyz #1").unwrap().approvals = vec![
+        Approval {
+            spender: Addr::unchecked("expired-bot"),
+            expires: Expiration::AtHeight(mock_env().block.height),
+        },
+        Approval {
+            spender: Addr::unchecked("bot"),
+            expires: Expiration::Never {},
+        },
+    ];
+    deps.querier.update_xyz_balances(xyz_balances);
+    let err = create_entry(deps.as_mut(), "expired-bot", "xyz #1").unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let _ = create_entry(deps.as_mut(), "bot", "xyz #1").unwrap();
+    let entry = from_binary::<Entry>(
+        &contract::query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Entry {
+                author_xyz_id: "xyz #1".to_string(),
+                coordinates: Coordinates { x: 1, y: 1, z: 1 },
+                index: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(entry.author_address, Addr::unchecked("bot"));
+
+    // operators approved for all of the owner's xyzs can post for any of them
+    let err = create_entry(deps.as_mut(), "wallet", "xyz #2").unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    deps.querier.update_xyz_operators(HashMap::from([(
+        ADDR2.to_string(),
+        vec![Cw721Approval {
+            spender: "wallet".to_string(),
+            expires: Expiration::Never {},
+        }],
+    )]));
+    let _ = create_entry(deps.as_mut(), "wallet", "xyz #2").unwrap();
+}
+
+#[test]
+fn pay_entry_fee() {
+    let xyz_balances = get_initial_xyz_balances();
+    let mut deps = mock_dependencies_xyz(xyz_balances.clone(), &[]);
+    setup_contract(deps.as_mut());
+
+    let create_entry_msg = ExecuteMsg::CreateEntry {
+        author_xyz_id: "xyz #1".to_string(),
+        text: "0xja was here.".to_string(),
+    };
+
+    // can't pay the fee in a denomination that isn't accepted
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR1, &[Coin::new(5000, "ukrw")]),
+        create_entry_msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::WrongDenom {});
+
+    // can't pay less than the fee in an accepted denomination
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR1, &[Coin::new(1000, "uusd"), Coin::new(5000, "ukrw")]),
+        create_entry_msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InsufficientFunds {});
+
+    // can pay in any accepted denomination, and the excess and unrelated coins are refunded
+    let res = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR1, &[Coin::new(2000, "uusd"), Coin::new(5000, "ukrw")]),
+        create_entry_msg.clone(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        BankMsg::Send {
+            to_address: ADDR1.to_string(),
+            amount: vec![Coin::new(500, "uusd"), Coin::new(5000, "ukrw")],
+        }
+        .into()
+    );
+
+    // nothing is refunded when the fee is paid exactly
+    let mut new_xyz_balances = xyz_balances.clone();
+    new_xyz_balances
+        .get_mut("xyz #1")
+        .unwrap()
+        .extension
+        .coordinates = Coordinates { x: 3, y: 3, z: 3 };
+    deps.querier.update_xyz_balances(new_xyz_balances);
+    let res = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR1, &[Coin::new(1000, "uluna")]),
+        create_entry_msg.clone(),
+    )
+    .unwrap();
+    assert!(res.messages.is_empty());
+
+    // everything sent is refunded when no fee is configured
+    contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+            entry_fees: Some(vec![]),
+            ..UpdateConfigMsg::default()
+        }),
+    )
+    .unwrap();
+    let mut new_xyz_balances = xyz_balances.clone();
+    new_xyz_balances
+        .get_mut("xyz #1")
+        .unwrap()
+        .extension
+        .coordinates = Coordinates { x: 4, y: 4, z: 4 };
+    deps.querier.update_xyz_balances(new_xyz_balances);
+    let res = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR1, &[Coin::new(1000, "uluna")]),
+        create_entry_msg,
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        BankMsg::Send {
+            to_address: ADDR1.to_string(),
+            amount: vec![Coin::new(1000, "uluna")],
+        }
+        .into()
+    );
+}
+
+#[test]
+fn pay_entry_fee_with_cw20() {
+    let mut deps = mock_dependencies_xyz(get_initial_xyz_balances(), &[]);
+    setup_contract(deps.as_mut());
+
+    let receive_msg = |amount: u128| {
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: ADDR1.to_string(),
+            amount: Uint128::new(amount),
+            msg: to_binary(&ReceiveMsg::CreateEntry {
+                author_xyz_id: "xyz #1".to_string(),
+                text: "0xja was here.".to_string(),
+            })
+            .unwrap(),
+        })
+    };
+
+    // can't pay with a token that isn't accepted
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("other-token-contract", &[]),
+        receive_msg(1000),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::WrongDenom {});
+
+    // can't pay less than the fee
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("token-contract", &[]),
+        receive_msg(999),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InsufficientFunds {});
+
+    // the token holder, not the token contract, must own the author xyz
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("token-contract", &[]),
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: ADDR2.to_string(),
+            amount: Uint128::new(1000),
+            msg: to_binary(&ReceiveMsg::CreateEntry {
+                author_xyz_id: "xyz #1".to_string(),
+                text: "0xja was here.".to_string(),
+            })
+            .unwrap(),
+        }),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // can create an entry by sending accepted tokens, and the excess is refunded
+    let res = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("token-contract", &[]),
+        receive_msg(1500),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        Response::default()
+            .add_attribute("action", "entry_created")
+            .add_attribute("xyz_id", "xyz #1".to_string())
+            .add_attribute("xyz_coordinates_x", "1")
+            .add_attribute("xyz_coordinates_y", "1")
+            .add_attribute("xyz_coordinates_z", "1")
+            .add_attribute("entry_index", "0")
+            .add_message(WasmMsg::Execute {
+                contract_addr: "token-contract".to_string(),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: ADDR1.to_string(),
+                    amount: Uint128::new(500),
+                })
+                .unwrap(),
+                funds: vec![],
+            })
+    );
+
+    // check that the entry was written by the token holder
+    let entry = from_binary::<Entry>(
+        &contract::query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Entry {
+                author_xyz_id: "xyz #1".to_string(),
+                coordinates: Coordinates { x: 1, y: 1, z: 1 },
+                index: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(entry.author_address, Addr::unchecked(ADDR1));
+}
+
+#[test]
+fn dynamic_pricing() {
+    let mut xyz_balances = get_initial_xyz_balances();
+    let xyz = xyz_balances.get_mut("xyz #2").unwrap();
+    xyz.extension.coordinates = Coordinates { x: 1, y: 1, z: 1 };
+    xyz.extension.arrival = Timestamp::from_nanos(0);
+    let mut deps = mock_dependencies_xyz(xyz_balances, &[]);
+    setup_contract(deps.as_mut());
+
+    let set_pricing = |deps: DepsMut, pricing: PricingMode| {
+        contract::execute(
+            deps,
+            mock_env(),
+            mock_info(OWNER, &[]),
+            ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+                pricing: Some(pricing),
+                ..UpdateConfigMsg::default()
+            }),
+        )
+        .unwrap();
+    };
+    let quote = |deps: Deps| {
+        from_binary::<EntryFeeQuoteResponse>(
+            &contract::query(
+                deps,
+                mock_env(),
+                QueryMsg::QuoteEntryFee {
+                    coordinates: Coordinates { x: 1, y: 1, z: 1 },
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    };
+    let expected_quote = |luna: u128, usd: u128, token: u128| EntryFeeQuoteResponse {
+        entry_fees: vec![Coin::new(luna, "uluna"), Coin::new(usd, "uusd")],
+        cw20_entry_fees: vec![Cw20CoinVerified {
+            address: Addr::unchecked("token-contract"),
+            amount: Uint128::new(token),
+        }],
+    };
+
+    // the first entry at a location costs the configured fee
+    set_pricing(deps.as_mut(), PricingMode::Linear { step_bps: 5000 });
+    assert_eq!(quote(deps.as_ref()), expected_quote(1000, 1500, 1000));
+    let _ = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR1, &[Coin::new(1000, "uluna")]),
+        ExecuteMsg::CreateEntry {
+            author_xyz_id: "xyz #1".to_string(),
+            text: "xyz #1 was here".to_string(),
+        },
+    )
+    .unwrap();
+
+    // each entry at a location makes the next one more expensive
+    assert_eq!(quote(deps.as_ref()), expected_quote(1500, 2250, 1500));
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR2, &[Coin::new(1000, "uluna")]),
+        ExecuteMsg::CreateEntry {
+            author_xyz_id: "xyz #2".to_string(),
+            text: "xyz #2 was here".to_string(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InsufficientFunds {});
+    let _ = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR2, &[Coin::new(1500, "uluna")]),
+        ExecuteMsg::CreateEntry {
+            author_xyz_id: "xyz #2".to_string(),
+            text: "xyz #2 was here".to_string(),
+        },
+    )
+    .unwrap();
+
+    // prices can compound instead
+    set_pricing(
+        deps.as_mut(),
+        PricingMode::Exponential { growth_bps: 10000 },
+    );
+    assert_eq!(quote(deps.as_ref()), expected_quote(4000, 6000, 4000));
+
+    // deleting an entry makes the location cheaper again
+    let _ = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR2, &[]),
+        ExecuteMsg::DeleteEntry {
+            author_xyz_id: "xyz #2".to_string(),
+            coordinates: Coordinates { x: 1, y: 1, z: 1 },
+            index: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(quote(deps.as_ref()), expected_quote(2000, 3000, 2000));
+
+    // flat pricing ignores how crowded a location is
+    set_pricing(deps.as_mut(), PricingMode::Flat);
+    assert_eq!(quote(deps.as_ref()), expected_quote(1000, 1500, 1000));
+}
+
+#[test]
+fn update_and_delete_entry() {
+    let mut deps = mock_dependencies_xyz(get_initial_xyz_balances(), &[]);
+    setup_contract(deps.as_mut());
+
+    let coordinates = Coordinates { x: 1, y: 1, z: 1 };
+    let _ = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR1, &[Coin::new(1000, "uluna")]),
+        ExecuteMsg::CreateEntry {
+            author_xyz_id: "xyz #1".to_string(),
+            text: "xyz #1 was here".to_string(),
+        },
+    )
+    .unwrap();
+
+    // can't update an entry that doesn't exist
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR1, &[]),
+        ExecuteMsg::UpdateEntry {
+            author_xyz_id: "xyz #1".to_string(),
+            coordinates: Coordinates { x: 9, y: 9, z: 9 },
+            index: None,
+            text: "xyz #1 was also here".to_string(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NotFound {});
+
+    // can't update an entry for an xyz you don't own
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR2, &[]),
+        ExecuteMsg::UpdateEntry {
+            author_xyz_id: "xyz #1".to_string(),
+            coordinates,
+            index: None,
+            text: "xyz #2 was here".to_string(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // can update an entry for an xyz you own
+    let res = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR1, &[]),
+        ExecuteMsg::UpdateEntry {
+            author_xyz_id: "xyz #1".to_string(),
+            coordinates,
+            index: None,
+            text: "xyz #1 was still here".to_string(),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        Response::default()
+            .add_attribute("action", "entry_updated")
+            .add_attribute("xyz_id", "xyz #1".to_string())
+            .add_attribute("xyz_coordinates_x", "1")
+            .add_attribute("xyz_coordinates_y", "1")
+            .add_attribute("xyz_coordinates_z", "1")
+            .add_attribute("entry_index", "0")
+    );
+    let entry = from_binary::<Entry>(
+        &contract::query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Entry {
+                author_xyz_id: "xyz #1".to_string(),
+                coordinates,
+                index: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        entry,
+        Entry {
+            author_xyz_id: "xyz #1".to_string(),
+            coordinates,
+            index: 0,
+            text: "xyz #1 was still here".to_string(),
+            seq: 1,
+            created_at: mock_env().block.time,
+            block_height: mock_env().block.height,
+            author_address: Addr::unchecked(ADDR1),
+            edit_count: 1,
+            last_edited: Some(mock_env().block.time),
+            tips: vec![],
+            pinned: false,
+            hidden_by_host: false,
+            hidden: false,
+            parent: None,
+            reactions: vec![],
+            score: 0,
+            late: false,
+        }
+    );
+
+    // can't delete an entry for an xyz you don't own
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR2, &[]),
+        ExecuteMsg::DeleteEntry {
+            author_xyz_id: "xyz #1".to_string(),
+            coordinates,
+            index: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // can delete an entry for an xyz you own
+    let _ = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR1, &[]),
+        ExecuteMsg::DeleteEntry {
+            author_xyz_id: "xyz #1".to_string(),
+            coordinates,
+            index: None,
+        },
+    )
+    .unwrap();
+
+    // check that the entry was removed from the indexes
+    let res = from_binary::<EntriesResponse>(
+        &contract::query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::EntriesForXyz {
+                author_xyz_id: "xyz #1".to_string(),
+                limit: None,
+                start_after: None,
+                include_hidden: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        EntriesResponse {
+            entries: vec![],
+            next_start_after: None,
+        }
+    );
+
+    // can create a new entry at the same location after deleting
+    let _ = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR1, &[Coin::new(1000, "uluna")]),
+        ExecuteMsg::CreateEntry {
+            author_xyz_id: "xyz #1".to_string(),
+            text: "xyz #1 is back".to_string(),
+        },
+    )
+    .unwrap();
+}
+
+#[test]
+fn reply_to_entry() {
+    let mut deps = mock_dependencies_xyz(get_initial_xyz_balances(), &[]);
+    setup_contract(deps.as_mut());
+    let parent = EntryRef {
+        author_xyz_id: "xyz #1".to_string(),
+        coordinates: Coordinates { x: 1, y: 1, z: 1 },
+        index: 0,
+    };
+
+    // can't reply to an entry that doesn't exist
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR2, &[Coin::new(1000, "uluna")]),
+        ExecuteMsg::ReplyToEntry {
+            parent_author_xyz_id: parent.author_xyz_id.clone(),
+            parent_coordinates: parent.coordinates,
+            parent_index: None,
+            author_xyz_id: "xyz #2".to_string(),
+            text: "hi xyz #1!".to_string(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NotFound {});
+
+    let _ = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR1, &[Coin::new(1000, "uluna")]),
+        ExecuteMsg::CreateEntry {
+            author_xyz_id: "xyz #1".to_string(),
+            text: "xyz #1 was here".to_string(),
+        },
+    )
+    .unwrap();
+
+    // repliers must own the replying xyz
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR1, &[Coin::new(1000, "uluna")]),
+        ExecuteMsg::ReplyToEntry {
+            parent_author_xyz_id: parent.author_xyz_id.clone(),
+            parent_coordinates: parent.coordinates,
+            parent_index: None,
+            author_xyz_id: "xyz #2".to_string(),
+            text: "hi xyz #1!".to_string(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // a reply is an entry at the replier's location that references its parent
+    let res = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR2, &[Coin::new(1000, "uluna")]),
+        ExecuteMsg::ReplyToEntry {
+            parent_author_xyz_id: parent.author_xyz_id.clone(),
+            parent_coordinates: parent.coordinates,
+            parent_index: None,
+            author_xyz_id: "xyz #2".to_string(),
+            text: "hi xyz #1!".to_string(),
+        },
+    )
+    .unwrap();
+    assert_eq!(res.attributes[6].key, "parent_xyz_id");
+    assert_eq!(res.attributes[6].value, "xyz #1");
+    let reply = from_binary::<Entry>(
+        &contract::query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Entry {
+                author_xyz_id: "xyz #2".to_string(),
+                coordinates: Coordinates { x: 2, y: 2, z: 2 },
+                index: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(reply.parent, Some(parent.clone()));
+
+    // the thread lists the parent's replies, and top-level entries aren't replies
+    let query_replies = |deps: Deps, parent: EntryRef| {
+        from_binary::<EntriesResponse>(
+            &contract::query(
+                deps,
+                mock_env(),
+                QueryMsg::Replies {
+                    parent,
+                    start_after: None,
+                    limit: None,
+                    include_hidden: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+        .entries
+    };
+    assert_eq!(
+        query_replies(deps.as_ref(), parent.clone()),
+        vec![reply.clone()]
+    );
+    assert!(query_replies(
+        deps.as_ref(),
+        EntryRef {
+            author_xyz_id: "xyz #2".to_string(),
+            coordinates: Coordinates { x: 2, y: 2, z: 2 },
+            index: 0,
+        }
+    )
+    .is_empty());
+
+    // a reply doesn't use up the replier's entry at its location, and is left out of the
+    // location's guestbook
+    let _ = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR2, &[Coin::new(1000, "uluna")]),
+        ExecuteMsg::CreateEntry {
+            author_xyz_id: "xyz #2".to_string(),
+            text: "xyz #2 was here".to_string(),
+        },
+    )
+    .unwrap();
+    let res = from_binary::<EntriesResponse>(
+        &contract::query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::EntriesForCoordinates {
+                coordinates: Coordinates { x: 2, y: 2, z: 2 },
+                start_after: None,
+                limit: None,
+                include_hidden: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.entries.len(), 1);
+    assert_eq!(res.entries[0].index, 1);
+    assert_eq!(res.entries[0].parent, None);
+
+    // nor does it count toward the location's entries or its ranking
+    let res = from_binary::<LocationResponse>(
+        &contract::query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Location {
+                coordinates: Coordinates { x: 2, y: 2, z: 2 },
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.entry_count, 1);
+    let res = from_binary::<EntriesResponse>(
+        &contract::query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::TopEntries {
+                coordinates: Some(Coordinates { x: 2, y: 2, z: 2 }),
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.entries.len(), 1);
+    assert_eq!(res.entries[0].parent, None);
+
+    // the parent's author can reply to its own entry, even though it can't sign again
+    let res = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR1, &[Coin::new(1000, "uluna")]),
+        ExecuteMsg::ReplyToEntry {
+            parent_author_xyz_id: parent.author_xyz_id.clone(),
+            parent_coordinates: parent.coordinates,
+            parent_index: None,
+            author_xyz_id: "xyz #1".to_string(),
+            text: "hi xyz #2!".to_string(),
+        },
+    )
+    .unwrap();
+    assert_eq!(res.attributes[5].key, "entry_index");
+    assert_eq!(res.attributes[5].value, "1");
+    let replies = query_replies(deps.as_ref(), parent.clone());
+    assert_eq!(replies.len(), 2);
+    assert_eq!(replies[0].author_xyz_id, "xyz #1");
+    assert_eq!(replies[1], reply);
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR1, &[Coin::new(1000, "uluna")]),
+        ExecuteMsg::CreateEntry {
+            author_xyz_id: "xyz #1".to_string(),
+            text: "xyz #1 was here again".to_string(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::EntryAlreadyExists {});
+
+    // deleting a reply doesn't free up a place under the cap
+    let _ = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR1, &[]),
+        ExecuteMsg::DeleteEntry {
+            author_xyz_id: "xyz #1".to_string(),
+            coordinates: Coordinates { x: 1, y: 1, z: 1 },
+            index: Some(1),
+        },
+    )
+    .unwrap();
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR1, &[Coin::new(1000, "uluna")]),
+        ExecuteMsg::CreateEntry {
+            author_xyz_id: "xyz #1".to_string(),
+            text: "xyz #1 was here again".to_string(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::EntryAlreadyExists {});
+}
+
+#[test]
+fn tip_entry() {
+    let xyz_balances = get_initial_xyz_balances();
+    let mut deps = mock_dependencies_xyz(xyz_balances.clone(), &[]);
+    setup_contract(deps.as_mut());
+
+    let _ = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR1, &[Coin::new(1000, "uluna")]),
+        ExecuteMsg::CreateEntry {
+            author_xyz_id: "xyz #1".to_string(),
+            text: "0xja was here.".to_string(),
+        },
+    )
+    .unwrap();
+
+    let tip_entry_msg = ExecuteMsg::TipEntry {
+        author_xyz_id: "xyz #1".to_string(),
+        coordinates: Coordinates { x: 1, y: 1, z: 1 },
+        index: None,
+    };
+
+    // can't tip without sending funds
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR2, &[]),
+        tip_entry_msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InsufficientFunds {});
+
+    // can't tip a non-existent entry
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR2, &[Coin::new(1000, "uluna")]),
+        ExecuteMsg::TipEntry {
+            author_xyz_id: "xyz #2".to_string(),
+            coordinates: Coordinates { x: 2, y: 2, z: 2 },
+            index: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NotFound {});
+
+    // tips go to the current owner of the author xyz, less the protocol's cut
+    let mut new_xyz_balances = xyz_balances.clone();
+    new_xyz_balances.get_mut("xyz #1").unwrap().owner = Addr::unchecked("addr3");
+    deps.querier.update_xyz_balances(new_xyz_balances);
+    let res = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR2, &[Coin::new(1000, "uluna"), Coin::new(10, "uusd")]),
+        tip_entry_msg.clone(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        Response::default()
+            .add_message(BankMsg::Send {
+                to_address: "addr3".to_string(),
+                amount: vec![Coin::new(950, "uluna"), Coin::new(10, "uusd")],
+            })
+            .add_attribute("action", "entry_tipped")
+            .add_attribute("xyz_id", "xyz #1")
+            .add_attribute("xyz_coordinates_x", "1")
+            .add_attribute("xyz_coordinates_y", "1")
+            .add_attribute("xyz_coordinates_z", "1")
+            .add_attribute("entry_index", "0")
+            .add_attribute("recipient", "addr3")
+    );
+
+    // check that tips accumulate on the entry
+    let _ = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR2, &[Coin::new(500, "uluna")]),
+        tip_entry_msg,
+    )
+    .unwrap();
+    let entry = from_binary::<Entry>(
+        &contract::query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Entry {
+                author_xyz_id: "xyz #1".to_string(),
+                coordinates: Coordinates { x: 1, y: 1, z: 1 },
+                index: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        entry.tips,
+        vec![Coin::new(1500, "uluna"), Coin::new(10, "uusd")]
+    );
+}
+
+#[test]
+fn react_to_entry() {
+    let mut deps = mock_dependencies_xyz(get_initial_xyz_balances(), &[]);
+    setup_contract(deps.as_mut());
+    for (sender, xyz_id) in [(ADDR1, "xyz #1"), (ADDR2, "xyz #2")] {
+        let _ = contract::execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(sender, &[Coin::new(1000, "uluna")]),
+            ExecuteMsg::CreateEntry {
+                author_xyz_id: xyz_id.to_string(),
+                text: format!("{} was here", xyz_id),
+            },
+        )
+        .unwrap();
+    }
+    let react = |deps: DepsMut, sender: &str, author_xyz_id: &str, reaction: &str| {
+        let (reactor_xyz_id, coordinates) = match author_xyz_id {
+            "xyz #1" => ("xyz #2", Coordinates { x: 1, y: 1, z: 1 }),
+            _ => ("xyz #1", Coordinates { x: 2, y: 2, z: 2 }),
+        };
+        contract::execute(
+            deps,
+            mock_env(),
+            mock_info(sender, &[]),
+            ExecuteMsg::ReactToEntry {
+                author_xyz_id: author_xyz_id.to_string(),
+                coordinates,
+                index: None,
+                reaction: reaction.to_string(),
+                reactor_xyz_id: reactor_xyz_id.to_string(),
+            },
+        )
+    };
+
+    // only allowed reactions can be left, by the owner of the reacting xyz
+    let err = react(deps.as_mut(), ADDR2, "xyz #1", "dislike").unwrap_err();
+    assert_eq!(err, ContractError::InvalidReaction {});
+    let err = react(deps.as_mut(), ADDR1, "xyz #1", "like").unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // each xyz can leave each kind of reaction once
+    let _ = react(deps.as_mut(), ADDR2, "xyz #1", "like").unwrap();
+    let err = react(deps.as_mut(), ADDR2, "xyz #1", "like").unwrap_err();
+    assert_eq!(err, ContractError::AlreadyReacted {});
+    let _ = react(deps.as_mut(), ADDR2, "xyz #1", "🔥").unwrap();
+    let _ = react(deps.as_mut(), ADDR1, "xyz #2", "like").unwrap();
+
+    let entry = from_binary::<Entry>(
+        &contract::query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Entry {
+                author_xyz_id: "xyz #1".to_string(),
+                coordinates: Coordinates { x: 1, y: 1, z: 1 },
+                index: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        entry.reactions,
+        vec![
+            ReactionCount {
+                reaction: "like".to_string(),
+                count: 1,
+            },
+            ReactionCount {
+                reaction: "🔥".to_string(),
+                count: 1,
+            },
+        ]
+    );
+    assert_eq!(entry.score, 2);
+
+    // top entries are ranked by score, across all locations or at one location
+    let query_top_entries = |deps: Deps, coordinates, start_after, limit| {
+        from_binary::<EntriesResponse>(
+            &contract::query(
+                deps,
+                mock_env(),
+                QueryMsg::TopEntries {
+                    coordinates,
+                    start_after,
+                    limit,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    };
+    let res = query_top_entries(deps.as_ref(), None, None, Some(1));
+    assert_eq!(res.entries, vec![entry]);
+    let res = query_top_entries(deps.as_ref(), None, res.next_start_after, Some(1));
+    assert_eq!(res.entries[0].author_xyz_id, "xyz #2");
+    assert_eq!(res.entries[0].score, 1);
+    assert_eq!(res.next_start_after, None);
+    let res = query_top_entries(
+        deps.as_ref(),
+        Some(Coordinates { x: 2, y: 2, z: 2 }),
+        None,
+        None,
+    );
+    assert_eq!(res.entries.len(), 1);
+    assert_eq!(res.entries[0].author_xyz_id, "xyz #2");
+}
+
+#[test]
+fn location_hosts() {
+    let mut xyz_balances = get_initial_xyz_balances();
+    xyz_balances
+        .get_mut("xyz #2")
+        .unwrap()
+        .extension
+        .coordinates = Coordinates { x: 1, y: 1, z: 1 };
+    let mut deps = mock_dependencies_xyz(xyz_balances.clone(), &[]);
+    setup_contract(deps.as_mut());
+    let location = Coordinates { x: 1, y: 1, z: 1 };
+
+    // can't claim a location with an xyz you don't own
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR2, &[]),
+        ExecuteMsg::ClaimLocation {
+            xyz_id: "xyz #1".to_string(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // the first xyz to claim a location becomes its host
+    let res = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR1, &[]),
+        ExecuteMsg::ClaimLocation {
+            xyz_id: "xyz #1".to_string(),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        Response::default()
+            .add_attribute("action", "location_claimed")
+            .add_attribute("xyz_id", "xyz #1")
+            .add_attribute("xyz_coordinates_x", "1")
+            .add_attribute("xyz_coordinates_y", "1")
+            .add_attribute("xyz_coordinates_z", "1")
+    );
+
+    // can't claim a location while its host is still there
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR2, &[]),
+        ExecuteMsg::ClaimLocation {
+            xyz_id: "xyz #2".to_string(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::LocationAlreadyClaimed {});
+
+    // only the host can set a surcharge, and it can't exceed the entry fee
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR2, &[]),
+        ExecuteMsg::SetLocationSurcharge {
+            coordinates: location,
+            surcharge_bps: 1000,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR1, &[]),
+        ExecuteMsg::SetLocationSurcharge {
+            coordinates: location,
+            surcharge_bps: 10001,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidSurcharge {});
+    let _ = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR1, &[]),
+        ExecuteMsg::SetLocationSurcharge {
+            coordinates: location,
+            surcharge_bps: 1000,
+        },
+    )
+    .unwrap();
+
+    // the surcharge is added to the entry fee and forwarded to the host
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR2, &[Coin::new(1000, "uluna")]),
+        ExecuteMsg::CreateEntry {
+            author_xyz_id: "xyz #2".to_string(),
+            text: "xyz #2 was here".to_string(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InsufficientFunds {});
+    let res = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR2, &[Coin::new(1100, "uluna")]),
+        ExecuteMsg::CreateEntry {
+            author_xyz_id: "xyz #2".to_string(),
+            text: "xyz #2 was here".to_string(),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        BankMsg::Send {
+            to_address: ADDR1.to_string(),
+            amount: vec![Coin::new(100, "uluna")],
+        }
+        .into()
+    );
+    let res = from_binary::<LocationResponse>(
+        &contract::query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Location {
+                coordinates: location,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        LocationResponse {
+            coordinates: location,
+            host_xyz_id: Some("xyz #1".to_string()),
+            surcharge_bps: 1000,
+            entry_count: 1,
+        }
+    );
+
+    // the host signs its own guestbook, and its entry gets a reaction
+    let _ = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR1, &[Coin::new(1100, "uluna")]),
+        ExecuteMsg::CreateEntry {
+            author_xyz_id: "xyz #1".to_string(),
+            text: "xyz #1 was here".to_string(),
+        },
+    )
+    .unwrap();
+    let _ = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR2, &[]),
+        ExecuteMsg::ReactToEntry {
+            author_xyz_id: "xyz #1".to_string(),
+            coordinates: location,
+            index: None,
+            reaction: "like".to_string(),
+            reactor_xyz_id: "xyz #2".to_string(),
+        },
+    )
+    .unwrap();
+
+    // only the host can pin and hide entries at the location
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR2, &[]),
+        ExecuteMsg::SetEntryPinned {
+            author_xyz_id: "xyz #2".to_string(),
+            coordinates: location,
+            index: None,
+            pinned: true,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    for msg in [
+        ExecuteMsg::SetEntryPinned {
+            author_xyz_id: "xyz #2".to_string(),
+            coordinates: location,
+            index: None,
+            pinned: true,
+        },
+        ExecuteMsg::SetEntryHiddenByHost {
+            author_xyz_id: "xyz #2".to_string(),
+            coordinates: location,
+            index: None,
+            hidden: true,
+        },
+    ] {
+        let _ = contract::execute(deps.as_mut(), mock_env(), mock_info(ADDR1, &[]), msg).unwrap();
+    }
+    let query_location_entries = |deps: Deps| {
+        from_binary::<EntriesResponse>(
+            &contract::query(
+                deps,
+                mock_env(),
+                QueryMsg::EntriesForCoordinates {
+                    coordinates: location,
+                    start_after: None,
+                    limit: None,
+                    include_hidden: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+        .entries
+    };
+
+    let query_top_entries = |deps: Deps, coordinates: Option<Coordinates>| {
+        from_binary::<EntriesResponse>(
+            &contract::query(
+                deps,
+                mock_env(),
+                QueryMsg::TopEntries {
+                    coordinates,
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+        .entries
+        .into_iter()
+        .map(|entry| entry.author_xyz_id)
+        .collect::<Vec<_>>()
+    };
+
+    // hidden entries are left out of the location's guestbook
+    let entries = query_location_entries(deps.as_ref());
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].author_xyz_id, "xyz #1");
+    let _ = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR1, &[]),
+        ExecuteMsg::SetEntryHiddenByHost {
+            author_xyz_id: "xyz #2".to_string(),
+            coordinates: location,
+            index: None,
+            hidden: false,
+        },
+    )
+    .unwrap();
+
+    // pinned entries come first in the location's guestbook and its ranking, but not overall
+    let entries = query_location_entries(deps.as_ref());
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].author_xyz_id, "xyz #2");
+    assert!(entries[0].pinned);
+    assert_eq!(
+        query_top_entries(deps.as_ref(), Some(location)),
+        vec!["xyz #2", "xyz #1"]
+    );
+    assert_eq!(
+        query_top_entries(deps.as_ref(), None),
+        vec!["xyz #1", "xyz #2"]
+    );
+
+    // the page cursor resumes after the pinned entries
+    let res = from_binary::<EntriesResponse>(
+        &contract::query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::EntriesForCoordinates {
+                coordinates: location,
+                start_after: None,
+                limit: Some(1),
+                include_hidden: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.entries[0].author_xyz_id, "xyz #2");
+    let res = from_binary::<EntriesResponse>(
+        &contract::query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::EntriesForCoordinates {
+                coordinates: location,
+                start_after: res.next_start_after,
+                limit: Some(1),
+                include_hidden: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.entries[0].author_xyz_id, "xyz #1");
+    assert_eq!(res.next_start_after, None);
+
+    // replies pay the flat entry fee, without the host's surcharge
+    let res = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR2, &[Coin::new(1000, "uluna")]),
+        ExecuteMsg::ReplyToEntry {
+            parent_author_xyz_id: "xyz #1".to_string(),
+            parent_coordinates: location,
+            parent_index: None,
+            author_xyz_id: "xyz #2".to_string(),
+            text: "hi xyz #1!".to_string(),
+        },
+    )
+    .unwrap();
+    assert!(res.messages.is_empty());
+
+    // the host moves away
+    xyz_balances
+        .get_mut("xyz #1")
+        .unwrap()
+        .extension
+        .coordinates = Coordinates { x: 3, y: 3, z: 3 };
+    deps.querier.update_xyz_balances(xyz_balances);
+
+    // a host that has moved away loses its powers and its surcharge
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR1, &[]),
+        ExecuteMsg::SetEntryPinned {
+            author_xyz_id: "xyz #1".to_string(),
+            coordinates: location,
+            index: None,
+            pinned: true,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let res = from_binary::<EntryFeeQuoteResponse>(
+        &contract::query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::QuoteEntryFee {
+                coordinates: location,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.entry_fees[0], Coin::new(1000, "uluna"));
+    let res = from_binary::<LocationResponse>(
+        &contract::query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Location {
+                coordinates: location,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.host_xyz_id, None);
+    assert_eq!(res.surcharge_bps, 0);
+
+    // another xyz can claim the location once the host has moved away
+    let _ = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR2, &[]),
+        ExecuteMsg::ClaimLocation {
+            xyz_id: "xyz #2".to_string(),
+        },
+    )
+    .unwrap();
+}
+
+#[test]
+fn moderation() {
+    let mut deps = mock_dependencies_xyz(get_initial_xyz_balances(), &[]);
+    setup_contract(deps.as_mut());
+    let location = Coordinates { x: 1, y: 1, z: 1 };
+    let _ = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR1, &[Coin::new(1000, "uluna")]),
+        ExecuteMsg::CreateEntry {
+            author_xyz_id: "xyz #1".to_string(),
+            text: "xyz #1 was here".to_string(),
+        },
+    )
+    .unwrap();
+
+    // only the owner can manage moderators
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR1, &[]),
+        ExecuteMsg::UpdateModerators {
+            add: vec![ADDR1.to_string()],
+            remove: vec![],
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let _ = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::UpdateModerators {
+            add: vec!["moderator".to_string()],
+            remove: vec![],
+        },
+    )
+    .unwrap();
+    let res = from_binary::<ModeratorsResponse>(
+        &contract::query(deps.as_ref(), mock_env(), QueryMsg::Moderators {}).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.moderators, vec!["moderator".to_string()]);
+
+    // reporters must own the xyz they report with
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR1, &[]),
+        ExecuteMsg::ReportEntry {
+            author_xyz_id: "xyz #1".to_string(),
+            coordinates: location,
+            index: None,
+            reporter_xyz_id: "xyz #2".to_string(),
+            reason: "spam".to_string(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // can't report an entry that doesn't exist
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR2, &[]),
+        ExecuteMsg::ReportEntry {
+            author_xyz_id: "xyz #2".to_string(),
+            coordinates: location,
+            index: None,
+            reporter_xyz_id: "xyz #2".to_string(),
+            reason: "spam".to_string(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NotFound {});
+
+    // reports go into the moderation queue
+    let res = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR2, &[]),
+        ExecuteMsg::ReportEntry {
+            author_xyz_id: "xyz #1".to_string(),
+            coordinates: location,
+            index: None,
+            reporter_xyz_id: "xyz #2".to_string(),
+            reason: "spam".to_string(),
+        },
+    )
+    .unwrap();
+    assert_eq!(res.attributes[1].value, "1");
+    let query_reports = |deps: Deps| {
+        from_binary::<ReportsResponse>(
+            &contract::query(
+                deps,
+                mock_env(),
+                QueryMsg::Reports {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+        .reports
+    };
+    assert_eq!(
+        query_reports(deps.as_ref()),
+        vec![Report {
+            id: 1,
+            author_xyz_id: "xyz #1".to_string(),
+            coordinates: location,
+            index: 0,
+            reporter_xyz_id: "xyz #2".to_string(),
+            reporter_address: Addr::unchecked(ADDR2),
+            reason: "spam".to_string(),
+            created_at: mock_env().block.time,
+        }]
+    );
+
+    // only moderators can hide entries
+    let hide_msg = ExecuteMsg::HideEntry {
+        author_xyz_id: "xyz #1".to_string(),
+        coordinates: location,
+        index: None,
+    };
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        hide_msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let _ = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("moderator", &[]),
+        hide_msg,
+    )
+    .unwrap();
+    assert!(query_reports(deps.as_ref()).is_empty());
+
+    // hidden entries are left out of listings unless asked for
+    let query_xyz_entries = |deps: Deps, include_hidden| {
+        from_binary::<EntriesResponse>(
+            &contract::query(
+                deps,
+                mock_env(),
+                QueryMsg::EntriesForXyz {
+                    author_xyz_id: "xyz #1".to_string(),
+                    start_after: None,
+                    limit: None,
+                    include_hidden,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+        .entries
+    };
+    assert!(query_xyz_entries(deps.as_ref(), None).is_empty());
+    let entries = query_xyz_entries(deps.as_ref(), Some(true));
+    assert_eq!(entries.len(), 1);
+    assert!(entries[0].hidden);
+    let query_region_entries = |deps: Deps, include_hidden| {
+        from_binary::<EntriesResponse>(
+            &contract::query(
+                deps,
+                mock_env(),
+                QueryMsg::EntriesInRegion {
+                    min: location,
+                    max: location,
+                    start_after: None,
+                    limit: None,
+                    include_hidden,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+        .entries
+    };
+    assert!(query_region_entries(deps.as_ref(), None).is_empty());
+    assert_eq!(query_region_entries(deps.as_ref(), Some(true)).len(), 1);
+    let res = from_binary::<EntriesResponse>(
+        &contract::query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::EntriesNear {
+                center: location,
+                radius: 1,
+                start_after: None,
+                limit: None,
+                include_hidden: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert!(res.entries.is_empty());
+
+    // moderators can restore hidden entries
+    let _ = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("moderator", &[]),
+        ExecuteMsg::RestoreEntry {
+            author_xyz_id: "xyz #1".to_string(),
+            coordinates: location,
+            index: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(query_xyz_entries(deps.as_ref(), None).len(), 1);
+
+    // removed moderators lose their powers
+    let _ = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::UpdateModerators {
+            add: vec![],
+            remove: vec!["moderator".to_string()],
+        },
+    )
+    .unwrap();
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("moderator", &[]),
+        ExecuteMsg::HideEntry {
+            author_xyz_id: "xyz #1".to_string(),
+            coordinates: location,
+            index: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+}
+
+#[test]
+fn content_policy() {
+    let mut deps = mock_dependencies_xyz(get_initial_xyz_balances(), &[]);
+    setup_contract(deps.as_mut());
+
+    let policy = ContentPolicy {
+        banned_substrings: vec!["Spam".to_string()],
+        disallowed_characters: vec![
+            CharacterCategory::Control,
+            CharacterCategory::ZeroWidth,
+            CharacterCategory::Bidi,
+        ],
+        max_newlines: Some(1),
+        block_urls: true,
+    };
+
+    // only the owner can set the content policy
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR1, &[]),
+        ExecuteMsg::UpdateContentPolicy(policy.clone()),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::UpdateContentPolicy(ContentPolicy {
+            banned_substrings: vec!["".to_string()],
+            ..ContentPolicy::default()
+        }),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidContentPolicy {});
+    let _ = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::UpdateContentPolicy(policy.clone()),
+    )
+    .unwrap();
+    let res = from_binary::<ContentPolicy>(
+        &contract::query(deps.as_ref(), mock_env(), QueryMsg::ContentPolicy {}).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res, policy);
+
+    // entries breaking a rule are rejected with the rule's name
+    for (text, rule) in [
+        ("buy SPAM now", "banned_substring"),
+        ("ding\u{7}", "control_character"),
+        ("hid\u{200B}den", "zero_width_character"),
+        ("\u{202E}olleh", "bidi_character"),
+        ("one\ntwo\nthree", "max_newlines"),
+        ("see https://example.com", "url"),
+        ("see WWW.example.com", "url"),
+    ] {
+        let err = contract::execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(ADDR1, &[Coin::new(1000, "uluna")]),
+            ExecuteMsg::CreateEntry {
+                author_xyz_id: "xyz #1".to_string(),
+                text: text.to_string(),
+            },
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::ContentPolicyViolation {
+                rule: rule.to_string()
+            }
+        );
+    }
+
+    // entries following the policy are accepted, and edits are held to it too
+    let _ = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR1, &[Coin::new(1000, "uluna")]),
+        ExecuteMsg::CreateEntry {
+            author_xyz_id: "xyz #1".to_string(),
+            text: "xyz #1 was here\nhello!".to_string(),
+        },
+    )
+    .unwrap();
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR1, &[]),
+        ExecuteMsg::UpdateEntry {
+            author_xyz_id: "xyz #1".to_string(),
+            coordinates: Coordinates { x: 1, y: 1, z: 1 },
+            index: None,
+            text: "spam".to_string(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::ContentPolicyViolation {
+            rule: "banned_substring".to_string()
+        }
     );
 }
 
@@ -238,11 +2476,599 @@ fn withdraw() {
     let mut deps = mock_dependencies(&contract_balance);
     setup_contract(deps.as_mut());
 
-    // non-owner can't withdraw
+    // non-owner can't withdraw
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR1, &[]),
+        ExecuteMsg::Withdraw {
+            amount: vec![Coin::new(100, "uluna")],
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // owner can withdraw
+    let res = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::Withdraw {
+            amount: vec![Coin::new(100, "uluna")],
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        BankMsg::Send {
+            amount: vec![Coin::new(100, "uluna")],
+            to_address: mock_info(OWNER, &[]).sender.to_string()
+        }
+        .into()
+    );
+
+    // non-owner can't withdraw CW20 tokens
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR1, &[]),
+        ExecuteMsg::WithdrawCw20 {
+            token_contract: "token-contract".to_string(),
+            amount: Uint128::new(100),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // owner can withdraw CW20 tokens
+    let res = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::WithdrawCw20 {
+            token_contract: "token-contract".to_string(),
+            amount: Uint128::new(100),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        WasmMsg::Execute {
+            contract_addr: "token-contract".to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: OWNER.to_string(),
+                amount: Uint128::new(100),
+            })
+            .unwrap(),
+            funds: vec![],
+        }
+        .into()
+    );
+
+    // once payees are configured, fees can only leave the contract through Distribute
+    contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::UpdatePayees {
+            payees: vec![PayeeMsg {
+                address: "treasury".to_string(),
+                share_bps: 10000,
+            }],
+        },
+    )
+    .unwrap();
+
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::Withdraw {
+            amount: vec![Coin::new(100, "uluna")],
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::PayeesConfigured {});
+
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::WithdrawCw20 {
+            token_contract: "token-contract".to_string(),
+            amount: Uint128::new(100),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::PayeesConfigured {});
+
+    // tokens that aren't accepted as entry fees can still be withdrawn
+    let res = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::WithdrawCw20 {
+            token_contract: "other-token".to_string(),
+            amount: Uint128::new(100),
+        },
+    )
+    .unwrap();
+    assert_eq!(res.messages.len(), 1);
+}
+
+#[test]
+fn distribute_fees() {
+    let contract_balance = vec![Coin::new(10001, "uluna"), Coin::new(3, "uusd")];
+    let mut deps = mock_dependencies_xyz(get_initial_xyz_balances(), &contract_balance);
+    deps.querier.update_cw20_balances(HashMap::from([(
+        "token-contract".to_string(),
+        Uint128::new(1001),
+    )]));
+    setup_contract(deps.as_mut());
+
+    // can't distribute before any payees are configured
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR1, &[]),
+        ExecuteMsg::Distribute {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NotFound {});
+
+    let payee_msg = |address: &str, share_bps: u32| PayeeMsg {
+        address: address.to_string(),
+        share_bps,
+    };
+
+    // non-owner can't update the payees
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR1, &[]),
+        ExecuteMsg::UpdatePayees {
+            payees: vec![payee_msg("treasury", 10000)],
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // can't set shares that don't add up to 100%, or list a payee twice
+    for payees in [
+        vec![payee_msg("treasury", 5000), payee_msg("dao", 4000)],
+        vec![payee_msg("treasury", 5000), payee_msg("treasury", 5000)],
+        vec![payee_msg("treasury", 10000), payee_msg("dao", 0)],
+    ] {
+        let err = contract::execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &[]),
+            ExecuteMsg::UpdatePayees { payees },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::InvalidPayeeShares {});
+    }
+
+    // owner can update the payees
+    let res = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::UpdatePayees {
+            payees: vec![
+                payee_msg("treasury", 5000),
+                payee_msg("dao", 3000),
+                payee_msg("author", 2000),
+            ],
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        Response::default()
+            .add_attribute("action", "payees_updated")
+            .add_attribute("payees", "treasury:5000,dao:3000,author:2000")
+    );
+
+    // check the pending balances, with the remainder from rounding going to the first payee
+    let cw20_coin = |amount: u128| Cw20CoinVerified {
+        address: Addr::unchecked("token-contract"),
+        amount: Uint128::new(amount),
+    };
+    let expected_payments = vec![
+        (
+            "treasury",
+            5000,
+            vec![Coin::new(5001, "uluna"), Coin::new(3, "uusd")],
+            vec![cw20_coin(501)],
+        ),
+        (
+            "dao",
+            3000,
+            vec![Coin::new(3000, "uluna")],
+            vec![cw20_coin(300)],
+        ),
+        (
+            "author",
+            2000,
+            vec![Coin::new(2000, "uluna")],
+            vec![cw20_coin(200)],
+        ),
+    ];
+    let res = from_binary::<PendingDistributionResponse>(
+        &contract::query(deps.as_ref(), mock_env(), QueryMsg::PendingDistribution {}).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        PendingDistributionResponse {
+            payments: expected_payments
+                .iter()
+                .map(|(address, share_bps, amount, cw20_amount)| PendingPayment {
+                    payee: Payee {
+                        address: Addr::unchecked(*address),
+                        share_bps: *share_bps,
+                    },
+                    amount: amount.clone(),
+                    cw20_amount: cw20_amount.clone(),
+                })
+                .collect(),
+        }
+    );
+
+    // anyone can distribute the contract balance, including accepted CW20 tokens, to the payees
+    let res = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR1, &[]),
+        ExecuteMsg::Distribute {},
+    )
+    .unwrap();
+    let mut expected = Response::default();
+    for (address, _, amount, cw20_amount) in expected_payments {
+        expected = expected.add_message(BankMsg::Send {
+            to_address: address.to_string(),
+            amount,
+        });
+        for coin in cw20_amount {
+            expected = expected.add_message(WasmMsg::Execute {
+                contract_addr: coin.address.to_string(),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: address.to_string(),
+                    amount: coin.amount,
+                })
+                .unwrap(),
+                funds: vec![],
+            });
+        }
+    }
+    assert_eq!(res, expected.add_attribute("action", "fees_distributed"));
+}
+
+#[test]
+fn instantiate_validates_config() {
+    let mut deps = mock_dependencies(&[]);
+
+    // can't instantiate with a zero character limit
+    let err = contract::instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        InstantiateMsg {
+            config: Config {
+                character_limit: 0,
+                ..get_initial_config()
+            },
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidCharacterLimit {});
+
+    // can't instantiate with a zero entry cap
+    let err = contract::instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        InstantiateMsg {
+            config: Config {
+                max_entries_per_location: 0,
+                ..get_initial_config()
+            },
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidMaxEntries {});
+
+    // can't instantiate with a cut of more than 100% of tips
+    let err = contract::instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        InstantiateMsg {
+            config: Config {
+                tip_fee_bps: 10001,
+                ..get_initial_config()
+            },
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidTipFee {});
+
+    // can't instantiate with an invalid cw20 token address
+    let err = contract::instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        InstantiateMsg {
+            config: Config {
+                cw20_entry_fees: vec![Cw20CoinVerified {
+                    address: Addr::unchecked("x"),
+                    amount: Uint128::new(1000),
+                }],
+                ..get_initial_config()
+            },
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Std(StdError::generic_err(
+            "Invalid input: human address too short"
+        ))
+    );
+
+    // nothing was saved by the failed attempts
+    let err = contract::query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap_err();
+    assert!(matches!(err, StdError::NotFound { .. }));
+}
+
+#[test]
+fn update_config() {
+    let mut deps = mock_dependencies(&[]);
+    setup_contract(deps.as_mut());
+
+    // non-owner can't update the config
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR1, &[]),
+        ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+            character_limit: Some(100),
+            ..UpdateConfigMsg::default()
+        }),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // can't set a zero character limit
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+            character_limit: Some(0),
+            ..UpdateConfigMsg::default()
+        }),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidCharacterLimit {});
+
+    // can't take a cut of more than 100% of tips
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+            tip_fee_bps: Some(10001),
+            ..UpdateConfigMsg::default()
+        }),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidTipFee {});
+
+    // can't set an invalid xyz NFT contract address
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+            xyz_nft_contract: Some("x".to_string()),
+            ..UpdateConfigMsg::default()
+        }),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Std(StdError::generic_err(
+            "Invalid input: human address too short"
+        ))
+    );
+
+    // owner can update the config
+    let res = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+            xyz_nft_contract: Some("xyz-nft-contract-v2".to_string()),
+            character_limit: Some(100),
+            limit_mode: Some(LimitMode::Graphemes),
+            entry_fees: Some(vec![Coin::new(2000, "uusd")]),
+            cw20_entry_fees: Some(vec![Cw20Coin {
+                address: "token-contract-v2".to_string(),
+                amount: Uint128::new(500),
+            }]),
+            tip_fee_bps: Some(1000),
+            pricing: Some(PricingMode::Linear { step_bps: 1000 }),
+            allowed_reactions: Some(vec!["like".to_string()]),
+            max_entries_per_location: Some(3),
+            entry_cooldown_seconds: Some(3600),
+            min_stay_seconds: Some(600),
+            late_signature_grace_seconds: Some(86400),
+        }),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        Response::default()
+            .add_attribute("action", "config_updated")
+            .add_attribute("old_xyz_nft_contract", "xyz-nft-contract")
+            .add_attribute("new_xyz_nft_contract", "xyz-nft-contract-v2")
+            .add_attribute("old_character_limit", "240")
+            .add_attribute("new_character_limit", "100")
+            .add_attribute("old_limit_mode", "bytes")
+            .add_attribute("new_limit_mode", "graphemes")
+            .add_attribute("old_entry_fees", "1000uluna,1500uusd")
+            .add_attribute("new_entry_fees", "2000uusd")
+            .add_attribute("old_cw20_entry_fees", "1000:token-contract")
+            .add_attribute("new_cw20_entry_fees", "500:token-contract-v2")
+            .add_attribute("old_tip_fee_bps", "500")
+            .add_attribute("new_tip_fee_bps", "1000")
+            .add_attribute("old_pricing", "flat")
+            .add_attribute("new_pricing", "linear:1000")
+            .add_attribute("old_allowed_reactions", "like,🔥")
+            .add_attribute("new_allowed_reactions", "like")
+            .add_attribute("old_max_entries_per_location", "1")
+            .add_attribute("new_max_entries_per_location", "3")
+            .add_attribute("old_entry_cooldown_seconds", "0")
+            .add_attribute("new_entry_cooldown_seconds", "3600")
+            .add_attribute("old_min_stay_seconds", "0")
+            .add_attribute("new_min_stay_seconds", "600")
+            .add_attribute("old_late_signature_grace_seconds", "0")
+            .add_attribute("new_late_signature_grace_seconds", "86400")
+    );
+
+    // check that the config was updated
+    let config = from_binary::<Config>(
+        &contract::query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        config,
+        Config {
+            xyz_nft_contract: Addr::unchecked("xyz-nft-contract-v2"),
+            character_limit: 100,
+            limit_mode: LimitMode::Graphemes,
+            entry_fees: vec![Coin::new(2000, "uusd")],
+            cw20_entry_fees: vec![Cw20CoinVerified {
+                address: Addr::unchecked("token-contract-v2"),
+                amount: Uint128::new(500),
+            }],
+            tip_fee_bps: 1000,
+            pricing: PricingMode::Linear { step_bps: 1000 },
+            allowed_reactions: vec!["like".to_string()],
+            max_entries_per_location: 3,
+            entry_cooldown_seconds: 3600,
+            min_stay_seconds: 600,
+            late_signature_grace_seconds: 86400,
+        }
+    );
+}
+
+fn query_owner(deps: Deps) -> OwnerResponse {
+    from_binary(&contract::query(deps, mock_env(), QueryMsg::Owner {}).unwrap()).unwrap()
+}
+
+#[test]
+fn transfer_ownership() {
+    let mut deps = mock_dependencies(&[]);
+    setup_contract(deps.as_mut());
+
+    // non-owner can't propose a new owner
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR1, &[]),
+        ExecuteMsg::ProposeNewOwner {
+            new_owner: ADDR1.to_string(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // owner can propose a new owner
+    let _ = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::ProposeNewOwner {
+            new_owner: ADDR1.to_string(),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        query_owner(deps.as_ref()),
+        OwnerResponse {
+            owner: Some(OWNER.to_string()),
+            pending_owner: Some(ADDR1.to_string()),
+        }
+    );
+
+    // only the proposed owner can accept
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR2, &[]),
+        ExecuteMsg::AcceptOwnership {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // owner can cancel the proposal, after which it can't be accepted
+    let _ = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::CancelOwnershipProposal {},
+    )
+    .unwrap();
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR1, &[]),
+        ExecuteMsg::AcceptOwnership {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::CancelOwnershipProposal {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NotFound {});
+
+    // proposed owner can accept a new proposal
+    let _ = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::ProposeNewOwner {
+            new_owner: ADDR1.to_string(),
+        },
+    )
+    .unwrap();
+    let _ = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR1, &[]),
+        ExecuteMsg::AcceptOwnership {},
+    )
+    .unwrap();
+    assert_eq!(
+        query_owner(deps.as_ref()),
+        OwnerResponse {
+            owner: Some(ADDR1.to_string()),
+            pending_owner: None,
+        }
+    );
+
+    // previous owner can no longer withdraw
     let err = contract::execute(
         deps.as_mut(),
         mock_env(),
-        mock_info(ADDR1, &[]),
+        mock_info(OWNER, &[]),
         ExecuteMsg::Withdraw {
             amount: vec![Coin::new(100, "uluna")],
         },
@@ -250,24 +3076,31 @@ fn withdraw() {
     .unwrap_err();
     assert_eq!(err, ContractError::Unauthorized {});
 
-    // owner can withdraw
-    let res = contract::execute(
+    // new owner can renounce ownership, after which nobody is the owner
+    let _ = contract::execute(
         deps.as_mut(),
         mock_env(),
-        mock_info(OWNER, &[]),
-        ExecuteMsg::Withdraw {
-            amount: vec![Coin::new(100, "uluna")],
-        },
+        mock_info(ADDR1, &[]),
+        ExecuteMsg::RenounceOwnership {},
     )
     .unwrap();
     assert_eq!(
-        res.messages[0].msg,
-        BankMsg::Send {
-            amount: vec![Coin::new(100, "uluna")],
-            to_address: mock_info(OWNER, &[]).sender.to_string()
+        query_owner(deps.as_ref()),
+        OwnerResponse {
+            owner: None,
+            pending_owner: None,
         }
-        .into()
+    );
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR1, &[]),
+        ExecuteMsg::Withdraw {
+            amount: vec![Coin::new(100, "uluna")],
+        },
     )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
 }
 
 #[test]
@@ -280,12 +3113,42 @@ fn read_entries() {
         author_xyz_id: "xyz #1".to_string(),
         text: "xyz #1 was here".to_string(),
         coordinates: Coordinates { x: 1, y: 1, z: 1 },
+        index: 0,
+        seq: 1,
+        created_at: mock_env().block.time,
+        block_height: mock_env().block.height,
+        author_address: Addr::unchecked(ADDR1),
+        edit_count: 0,
+        last_edited: None,
+        tips: vec![],
+        pinned: false,
+        hidden_by_host: false,
+        hidden: false,
+        parent: None,
+        reactions: vec![],
+        score: 0,
+        late: false,
     };
 
     let addr2_entry = Entry {
         author_xyz_id: "xyz #2".to_string(),
         text: "xyz #2 was here".to_string(),
         coordinates: Coordinates { x: 2, y: 2, z: 2 },
+        index: 0,
+        seq: 2,
+        created_at: mock_env().block.time,
+        block_height: mock_env().block.height,
+        author_address: Addr::unchecked(ADDR2),
+        edit_count: 0,
+        last_edited: None,
+        tips: vec![],
+        pinned: false,
+        hidden_by_host: false,
+        hidden: false,
+        parent: None,
+        reactions: vec![],
+        score: 0,
+        late: false,
     };
 
     // write entries for both xyz owners
@@ -319,6 +3182,7 @@ fn read_entries() {
                 author_xyz_id: addr1_entry.author_xyz_id.clone(),
                 limit: None,
                 start_after: None,
+                include_hidden: None,
             },
         )
         .unwrap(),
@@ -327,7 +3191,8 @@ fn read_entries() {
     assert_eq!(
         res,
         EntriesResponse {
-            entries: vec![addr1_entry.clone()]
+            entries: vec![addr1_entry.clone()],
+            next_start_after: None,
         }
     );
 
@@ -340,6 +3205,7 @@ fn read_entries() {
                 author_xyz_id: addr2_entry.author_xyz_id.clone(),
                 limit: None,
                 start_after: None,
+                include_hidden: None,
             },
         )
         .unwrap(),
@@ -348,7 +3214,8 @@ fn read_entries() {
     assert_eq!(
         res,
         EntriesResponse {
-            entries: vec![addr2_entry.clone()]
+            entries: vec![addr2_entry.clone()],
+            next_start_after: None,
         }
     );
 
@@ -358,9 +3225,10 @@ fn read_entries() {
             deps.as_ref(),
             mock_env(),
             QueryMsg::EntriesForCoordinates {
-                coordinates: addr1_entry.coordinates.clone(),
+                coordinates: addr1_entry.coordinates,
                 limit: None,
                 start_after: None,
+                include_hidden: None,
             },
         )
         .unwrap(),
@@ -369,7 +3237,8 @@ fn read_entries() {
     assert_eq!(
         res,
         EntriesResponse {
-            entries: vec![addr1_entry.clone()]
+            entries: vec![addr1_entry.clone()],
+            next_start_after: None,
         }
     );
 
@@ -379,9 +3248,164 @@ fn read_entries() {
             deps.as_ref(),
             mock_env(),
             QueryMsg::EntriesForCoordinates {
-                coordinates: addr2_entry.coordinates.clone(),
+                coordinates: addr2_entry.coordinates,
+                limit: None,
+                start_after: None,
+                include_hidden: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        EntriesResponse {
+            entries: vec![addr2_entry.clone()],
+            next_start_after: None,
+        }
+    );
+}
+
+#[test]
+fn migrate() {
+    let mut deps = mock_dependencies_xyz(get_initial_xyz_balances(), &[]);
+    setup_contract(deps.as_mut());
+
+    // populate the store with a config and an entry in the layout used before 0.2.0
+    CONFIG_V0_1
+        .save(
+            deps.as_mut().storage,
+            &ConfigV0_1 {
+                xyz_nft_contract: Addr::unchecked("xyz-nft-contract"),
+                character_limit: 240,
+                entry_fee: Coin::new(1000, "uluna"),
+            },
+        )
+        .unwrap();
+    let legacy_entry = EntryV0_1 {
+        author_xyz_id: "xyz #1".to_string(),
+        coordinates: Coordinates { x: 1, y: 1, z: 1 },
+        text: "xyz #1 was here".to_string(),
+    };
+    let legacy_key = [
+        legacy_entry.author_xyz_id.as_bytes(),
+        &legacy_entry.coordinates.to_bytes(),
+    ]
+    .concat();
+    entries_v0_1()
+        .save(deps.as_mut().storage, &legacy_key, &legacy_entry)
+        .unwrap();
+    let contract_name = get_contract_version(deps.as_ref().storage)
+        .unwrap()
+        .contract;
+    let contract_version = env!("CARGO_PKG_VERSION");
+
+    // can't migrate from a different contract
+    set_contract_version(deps.as_mut().storage, "crates.io:other-contract", "0.0.1").unwrap();
+    let err = contract::migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::CannotMigrate {
+            previous_contract: "crates.io:other-contract".to_string()
+        }
+    );
+
+    // can't migrate from a newer version
+    set_contract_version(deps.as_mut().storage, contract_name.clone(), "99.0.0").unwrap();
+    let err = contract::migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::CannotDowngrade {
+            previous_version: "99.0.0".to_string(),
+            new_version: contract_version.to_string(),
+        }
+    );
+
+    // can migrate from an older version of this contract
+    set_contract_version(deps.as_mut().storage, contract_name, "0.1.0").unwrap();
+    let res = contract::migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    assert_eq!(
+        res,
+        Response::default()
+            .add_attribute("method", "migrate")
+            .add_attribute("previous_version", "0.1.0")
+            .add_attribute("new_version", contract_version)
+            .add_attribute("migrations_applied", "0.2.0")
+    );
+    assert_eq!(
+        get_contract_version(deps.as_ref().storage).unwrap().version,
+        contract_version
+    );
+
+    // check that the config was migrated to the current layout
+    let config = from_binary::<Config>(
+        &contract::query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        config,
+        Config {
+            xyz_nft_contract: Addr::unchecked("xyz-nft-contract"),
+            character_limit: 240,
+            limit_mode: LimitMode::Bytes,
+            entry_fees: vec![Coin::new(1000, "uluna")],
+            cw20_entry_fees: vec![],
+            tip_fee_bps: 0,
+            pricing: PricingMode::Flat,
+            allowed_reactions: vec![],
+            max_entries_per_location: 1,
+            entry_cooldown_seconds: 0,
+            min_stay_seconds: 0,
+            late_signature_grace_seconds: 0,
+        }
+    );
+
+    // check that existing entries were migrated to the current layout
+    let migrated_entry = Entry {
+        author_xyz_id: "xyz #1".to_string(),
+        coordinates: Coordinates { x: 1, y: 1, z: 1 },
+        index: 0,
+        text: "xyz #1 was here".to_string(),
+        seq: 1,
+        created_at: Timestamp::from_nanos(0),
+        block_height: 0,
+        author_address: Addr::unchecked(""),
+        edit_count: 0,
+        last_edited: None,
+        tips: vec![],
+        pinned: false,
+        hidden_by_host: false,
+        hidden: false,
+        parent: None,
+        reactions: vec![],
+        score: 0,
+        late: false,
+    };
+    let entry = from_binary::<Entry>(
+        &contract::query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Entry {
+                author_xyz_id: "xyz #1".to_string(),
+                coordinates: Coordinates { x: 1, y: 1, z: 1 },
+                index: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(entry, migrated_entry);
+
+    // check that the indexes were rebuilt
+    let res = from_binary::<EntriesResponse>(
+        &contract::query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::EntriesForCoordinates {
+                coordinates: Coordinates { x: 1, y: 1, z: 1 },
                 limit: None,
                 start_after: None,
+                include_hidden: None,
             },
         )
         .unwrap(),
@@ -390,7 +3414,457 @@ fn read_entries() {
     assert_eq!(
         res,
         EntriesResponse {
-            entries: vec![addr2_entry.clone()]
+            entries: vec![migrated_entry],
+            next_start_after: None,
+        }
+    );
+
+    // check that each entry was recorded in its author's posting history
+    let history = POSTING_HISTORY
+        .load(
+            &deps.storage,
+            ("xyz #1", &Coordinates { x: 1, y: 1, z: 1 }.to_bytes()),
+        )
+        .unwrap();
+    assert_eq!(
+        history,
+        PostingHistory {
+            next_index: 1,
+            last_signed_at: Some(Timestamp::from_nanos(0)),
+            signature_count: 1,
+        }
+    );
+}
+
+#[test]
+fn read_all_entries() {
+    let xyz_balances = get_initial_xyz_balances();
+    let mut deps = mock_dependencies_xyz(xyz_balances.clone(), &[]);
+    setup_contract(deps.as_mut());
+
+    // write entries for xyz #1, xyz #2, then xyz #1 again at a new location
+    let _ = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR1, &[Coin::new(1000, "uluna")]),
+        ExecuteMsg::CreateEntry {
+            author_xyz_id: "xyz #1".to_string(),
+            text: "first".to_string(),
+        },
+    )
+    .unwrap();
+    let _ = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR2, &[Coin::new(1000, "uluna")]),
+        ExecuteMsg::CreateEntry {
+            author_xyz_id: "xyz #2".to_string(),
+            text: "second".to_string(),
+        },
+    )
+    .unwrap();
+    let mut new_xyz_balances = xyz_balances;
+    let moved_xyz = new_xyz_balances.get_mut("xyz #1").unwrap();
+    moved_xyz.extension.coordinates = Coordinates { x: 3, y: 3, z: 3 };
+    deps.querier.update_xyz_balances(new_xyz_balances);
+    let _ = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR1, &[Coin::new(1000, "uluna")]),
+        ExecuteMsg::CreateEntry {
+            author_xyz_id: "xyz #1".to_string(),
+            text: "third".to_string(),
+        },
+    )
+    .unwrap();
+
+    let query_all_entries = |start_after: Option<Binary>, limit: Option<u32>, order| {
+        let res = from_binary::<EntriesResponse>(
+            &contract::query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::AllEntries {
+                    start_after,
+                    limit,
+                    order,
+                    include_hidden: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let entries = res
+            .entries
+            .into_iter()
+            .map(|entry| (entry.seq, entry.text))
+            .collect::<Vec<_>>();
+        (entries, res.next_start_after)
+    };
+
+    // read all entries in creation order
+    let (entries, next_start_after) = query_all_entries(None, None, None);
+    assert_eq!(
+        entries,
+        vec![
+            (1, "first".to_string()),
+            (2, "second".to_string()),
+            (3, "third".to_string())
+        ]
+    );
+    assert_eq!(next_start_after, None);
+
+    // read the latest entries first, then page on to the oldest
+    let (entries, next_start_after) = query_all_entries(None, Some(2), Some(OrderBy::Descending));
+    assert_eq!(
+        entries,
+        vec![(3, "third".to_string()), (2, "second".to_string())]
+    );
+    let (entries, next_start_after) =
+        query_all_entries(next_start_after, Some(2), Some(OrderBy::Descending));
+    assert_eq!(entries, vec![(1, "first".to_string())]);
+    assert_eq!(next_start_after, None);
+
+    // page through entries in creation order
+    let (entries, next_start_after) = query_all_entries(None, Some(1), Some(OrderBy::Ascending));
+    assert_eq!(entries, vec![(1, "first".to_string())]);
+    let (entries, _) = query_all_entries(next_start_after, Some(1), Some(OrderBy::Ascending));
+    assert_eq!(entries, vec![(2, "second".to_string())]);
+}
+
+#[test]
+fn paginate_entries() {
+    // xyz #1 visits more locations than fit on a single page, and more xyzs than fit on a single
+    // page visit [0,0,0]
+    let mut xyz_balances = get_initial_xyz_balances();
+    let template = xyz_balances.get("xyz #1").unwrap().clone();
+    let num_entries = 35;
+    for i in 0..num_entries {
+        let mut xyz = template.clone();
+        xyz.name = format!("xyz #{}", 100 + i);
+        xyz.extension.coordinates = Coordinates { x: 0, y: 0, z: 0 };
+        xyz_balances.insert(xyz.name.clone(), xyz);
+    }
+    let mut deps = mock_dependencies_xyz(xyz_balances.clone(), &[]);
+    setup_contract(deps.as_mut());
+
+    for i in 0..num_entries {
+        let mut new_xyz_balances = xyz_balances.clone();
+        new_xyz_balances
+            .get_mut("xyz #1")
+            .unwrap()
+            .extension
+            .coordinates = Coordinates { x: i, y: i, z: i };
+        deps.querier.update_xyz_balances(new_xyz_balances);
+        for author_xyz_id in ["xyz #1".to_string(), format!("xyz #{}", 100 + i)] {
+            let _ = contract::execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(ADDR1, &[Coin::new(1000, "uluna")]),
+                ExecuteMsg::CreateEntry {
+                    author_xyz_id,
+                    text: "was here".to_string(),
+                },
+            )
+            .unwrap();
+        }
+    }
+
+    // page through xyz #1's entries, passing back each page's cursor
+    let mut start_after = None;
+    let mut seen = vec![];
+    let mut pages = 0;
+    loop {
+        let res = from_binary::<EntriesResponse>(
+            &contract::query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::EntriesForXyz {
+                    author_xyz_id: "xyz #1".to_string(),
+                    start_after,
+                    limit: Some(100),
+                    include_hidden: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        pages += 1;
+        seen.extend(res.entries.into_iter().map(|entry| entry.coordinates));
+        start_after = res.next_start_after;
+        if start_after.is_none() {
+            break;
+        }
+    }
+    assert_eq!(pages, 2);
+    assert_eq!(
+        seen,
+        (0..num_entries)
+            .map(|i| Coordinates { x: i, y: i, z: i })
+            .collect::<Vec<_>>()
+    );
+
+    // page through the entries at [0,0,0], passing back each page's cursor
+    let mut start_after = None;
+    let mut seen = vec![];
+    let mut pages = 0;
+    loop {
+        let res = from_binary::<EntriesResponse>(
+            &contract::query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::EntriesForCoordinates {
+                    coordinates: Coordinates { x: 0, y: 0, z: 0 },
+                    start_after,
+                    limit: None,
+                    include_hidden: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        pages += 1;
+        seen.extend(res.entries.into_iter().map(|entry| entry.author_xyz_id));
+        start_after = res.next_start_after;
+        if start_after.is_none() {
+            break;
+        }
+    }
+    // xyz #1's first entry is also at [0,0,0]
+    assert_eq!(pages, 4);
+    assert_eq!(seen.len(), num_entries as usize + 1);
+    let mut expected = seen.clone();
+    expected.sort();
+    expected.dedup();
+    assert_eq!(seen.len(), expected.len());
+}
+
+#[test]
+fn read_entries_in_region() {
+    let xyz_balances = get_initial_xyz_balances();
+    let mut deps = mock_dependencies_xyz(xyz_balances.clone(), &[]);
+    setup_contract(deps.as_mut());
+
+    // write entries for xyz #2 at [2,2,2], and for xyz #1 at several locations on both sides
+    // of the origin
+    let _ = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR2, &[Coin::new(1000, "uluna")]),
+        ExecuteMsg::CreateEntry {
+            author_xyz_id: "xyz #2".to_string(),
+            text: "xyz #2 was here".to_string(),
+        },
+    )
+    .unwrap();
+    for coordinates in [
+        Coordinates { x: 1, y: 1, z: 1 },
+        Coordinates { x: 3, y: 3, z: 3 },
+        Coordinates { x: -20, y: 5, z: 5 },
+        Coordinates {
+            x: 40,
+            y: 40,
+            z: 40,
+        },
+    ] {
+        let mut new_xyz_balances = xyz_balances.clone();
+        new_xyz_balances
+            .get_mut("xyz #1")
+            .unwrap()
+            .extension
+            .coordinates = coordinates;
+        deps.querier.update_xyz_balances(new_xyz_balances);
+        let _ = contract::execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(ADDR1, &[Coin::new(1000, "uluna")]),
+            ExecuteMsg::CreateEntry {
+                author_xyz_id: "xyz #1".to_string(),
+                text: "xyz #1 was here".to_string(),
+            },
+        )
+        .unwrap();
+    }
+
+    let query_coordinates = |msg: QueryMsg| {
+        let res = from_binary::<EntriesResponse>(
+            &contract::query(deps.as_ref(), mock_env(), msg).unwrap(),
+        )
+        .unwrap();
+        let coordinates = res
+            .entries
+            .into_iter()
+            .map(|entry| entry.coordinates)
+            .collect::<Vec<_>>();
+        (coordinates, res.next_start_after)
+    };
+
+    // read entries within a small region
+    let (coordinates, _) = query_coordinates(QueryMsg::EntriesInRegion {
+        min: Coordinates { x: 0, y: 0, z: 0 },
+        max: Coordinates { x: 2, y: 2, z: 2 },
+        start_after: None,
+        limit: None,
+        include_hidden: None,
+    });
+    assert_eq!(
+        coordinates,
+        vec![
+            Coordinates { x: 1, y: 1, z: 1 },
+            Coordinates { x: 2, y: 2, z: 2 }
+        ]
+    );
+
+    // page through every entry within a region spanning several buckets
+    let mut start_after = None;
+    let mut seen = vec![];
+    loop {
+        let (coordinates, next_start_after) = query_coordinates(QueryMsg::EntriesInRegion {
+            min: Coordinates {
+                x: -30,
+                y: -30,
+                z: -30,
+            },
+            max: Coordinates {
+                x: 50,
+                y: 50,
+                z: 50,
+            },
+            start_after,
+            limit: Some(2),
+            include_hidden: None,
+        });
+        seen.extend(coordinates);
+        start_after = next_start_after;
+        if start_after.is_none() {
+            break;
         }
+    }
+    assert_eq!(seen.len(), 5);
+    assert!(seen.contains(&Coordinates { x: -20, y: 5, z: 5 }));
+    assert!(seen.contains(&Coordinates {
+        x: 40,
+        y: 40,
+        z: 40
+    }));
+
+    // read entries near a location
+    let (coordinates, _) = query_coordinates(QueryMsg::EntriesNear {
+        center: Coordinates { x: 2, y: 2, z: 2 },
+        radius: 1,
+        start_after: None,
+        limit: None,
+        include_hidden: None,
+    });
+    assert_eq!(coordinates, vec![Coordinates { x: 2, y: 2, z: 2 }]);
+    let (coordinates, _) = query_coordinates(QueryMsg::EntriesNear {
+        center: Coordinates { x: 2, y: 2, z: 2 },
+        radius: 2,
+        start_after: None,
+        limit: None,
+        include_hidden: None,
+    });
+    // within a bucket, entries are ordered by primary key, so xyz #1's entries come first
+    assert_eq!(
+        coordinates,
+        vec![
+            Coordinates { x: 1, y: 1, z: 1 },
+            Coordinates { x: 3, y: 3, z: 3 },
+            Coordinates { x: 2, y: 2, z: 2 }
+        ]
     );
+
+    // can't query an inverted or oversized region
+    let err = contract::query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::EntriesInRegion {
+            min: Coordinates { x: 2, y: 2, z: 2 },
+            max: Coordinates { x: 0, y: 0, z: 0 },
+            start_after: None,
+            limit: None,
+            include_hidden: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, StdError::generic_err("Invalid region"));
+    let err = contract::query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::EntriesNear {
+            center: Coordinates { x: 0, y: 0, z: 0 },
+            radius: 10000,
+            start_after: None,
+            limit: None,
+            include_hidden: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, StdError::generic_err("Region too large"));
+    let err = contract::query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::EntriesInRegion {
+            min: Coordinates {
+                x: i64::MIN,
+                y: i64::MIN,
+                z: i64::MIN,
+            },
+            max: Coordinates {
+                x: i64::MAX,
+                y: i64::MAX,
+                z: i64::MAX,
+            },
+            start_after: None,
+            limit: None,
+            include_hidden: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, StdError::generic_err("Region too large"));
+
+    // searching near the edge of the coordinate range doesn't overflow
+    let (coordinates, _) = query_coordinates(QueryMsg::EntriesNear {
+        center: Coordinates {
+            x: i64::MAX,
+            y: 0,
+            z: 0,
+        },
+        radius: 1,
+        start_after: None,
+        limit: None,
+        include_hidden: None,
+    });
+    assert!(coordinates.is_empty());
+
+    // replies are left out of regions, since they're listed in their parent's thread
+    let _ = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADDR2, &[Coin::new(1000, "uluna")]),
+        ExecuteMsg::ReplyToEntry {
+            parent_author_xyz_id: "xyz #1".to_string(),
+            parent_coordinates: Coordinates { x: 1, y: 1, z: 1 },
+            parent_index: None,
+            author_xyz_id: "xyz #2".to_string(),
+            text: "hi xyz #1!".to_string(),
+        },
+    )
+    .unwrap();
+    let res = from_binary::<EntriesResponse>(
+        &contract::query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::EntriesInRegion {
+                min: Coordinates { x: 0, y: 0, z: 0 },
+                max: Coordinates { x: 2, y: 2, z: 2 },
+                start_after: None,
+                limit: None,
+                include_hidden: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.entries.len(), 2);
+    assert!(res.entries.iter().all(|entry| entry.parent.is_none()));
 }
@@ -15,12 +15,66 @@ pub enum ContractError {
     #[error("Entry Already Exists")]
     EntryAlreadyExists {},
 
-    #[error("Character Limit Exceeded")]
-    CharacterLimitExceeded {},
+    #[error("Character Limit Exceeded: {length} > {limit}")]
+    CharacterLimitExceeded { length: u32, limit: u32 },
+
+    #[error("Content Policy Violation: {rule}")]
+    ContentPolicyViolation { rule: String },
+
+    #[error("Entry Cooldown Active")]
+    EntryCooldownActive {},
+
+    #[error("Minimum Stay Not Met")]
+    MinimumStayNotMet {},
+
+    #[error("Late Signature Unavailable")]
+    LateSignatureUnavailable {},
+
+    #[error("Invalid Max Entries Per Location")]
+    InvalidMaxEntries {},
+
+    #[error("Invalid Content Policy")]
+    InvalidContentPolicy {},
+
+    #[error("Invalid Character Limit")]
+    InvalidCharacterLimit {},
 
     #[error("Insufficient Funds")]
     InsufficientFunds {},
 
+    #[error("Wrong Denomination")]
+    WrongDenom {},
+
+    #[error("Invalid Tip Fee")]
+    InvalidTipFee {},
+
+    #[error("Invalid Payee Shares")]
+    InvalidPayeeShares {},
+
+    #[error("Payees Configured")]
+    PayeesConfigured {},
+
+    #[error("Invalid Surcharge")]
+    InvalidSurcharge {},
+
+    #[error("Invalid Reaction")]
+    InvalidReaction {},
+
+    #[error("Already Reacted")]
+    AlreadyReacted {},
+
+    #[error("Location Already Claimed")]
+    LocationAlreadyClaimed {},
+
     #[error("Relocation in Progress")]
     RelocationInProgress {},
+
+    #[error("Cannot Migrate From Contract {previous_contract}")]
+    CannotMigrate { previous_contract: String },
+
+    #[error("Cannot Migrate From Version {previous_version} To {new_version}")]
+    CannotDowngrade {
+        previous_version: String,
+        new_version: String,
+    },
 }
@@ -0,0 +1,176 @@
+// This file defines the ordered list of data migrations run by the contract's migrate entry
+// point. Each migration is keyed by the contract version that introduced the storage layout it
+// produces, and is only applied when migrating from a stored version older than that one.
+//
+// When a release changes the layout of CONFIG, OWNER or entries(), add a new function below and
+// register it at the end of MIGRATIONS.
+
+use collectxyz::nft::Coordinates;
+use cosmwasm_std::{Addr, Coin, Env, Order, StdError, StdResult, Storage, Timestamp};
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, MultiIndex};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+
+use crate::state::{
+    entries, entries_key, Config, Entry, LimitMode, PostingHistory, PricingMode, CONFIG, ENTRY_SEQ,
+    LOCATION_ENTRY_COUNTS, POSTING_HISTORY,
+};
+
+type Migration = fn(&mut dyn Storage, &Env) -> StdResult<()>;
+
+// Migrations ordered from oldest to newest version.
+const MIGRATIONS: &[(&str, Migration)] = &[("0.2.0", migrate_v0_2_0)];
+
+pub fn parse_version(version: &str) -> StdResult<Version> {
+    Version::parse(version).map_err(|e| StdError::generic_err(format!("Invalid version: {}", e)))
+}
+
+// Run every migration newer than the stored version, in order, returning the versions applied.
+pub fn run_migrations(
+    storage: &mut dyn Storage,
+    env: &Env,
+    from_version: &Version,
+) -> StdResult<Vec<String>> {
+    let mut applied = vec![];
+    for (version, migration) in MIGRATIONS {
+        if parse_version(version)? > *from_version {
+            migration(storage, env)?;
+            applied.push(version.to_string());
+        }
+    }
+    Ok(applied)
+}
+
+// The config layout used before version 0.2.0, which accepted a single entry fee.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ConfigV0_1 {
+    pub xyz_nft_contract: Addr,
+    pub character_limit: u32,
+    pub entry_fee: Coin,
+}
+
+pub const CONFIG_V0_1: Item<ConfigV0_1> = Item::new("config");
+
+// The guestbook entry layout used before version 0.2.0.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct EntryV0_1 {
+    pub author_xyz_id: String,
+    pub coordinates: Coordinates,
+    pub text: String,
+}
+
+pub struct EntryIndexesV0_1<'a> {
+    pub author_xyz_id: MultiIndex<'a, (String, Vec<u8>), EntryV0_1>,
+    pub coordinates: MultiIndex<'a, (Vec<u8>, Vec<u8>), EntryV0_1>,
+}
+
+impl<'a> IndexList<EntryV0_1> for EntryIndexesV0_1<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<EntryV0_1>> + '_> {
+        let v: Vec<&dyn Index<EntryV0_1>> = vec![&self.author_xyz_id, &self.coordinates];
+        Box::new(v.into_iter())
+    }
+}
+
+// The indexed map of guestbook entries as laid out before version 0.2.0, keyed by the raw
+// concatenation of the xyz token ID and coordinates.
+pub fn entries_v0_1<'a>() -> IndexedMap<'a, &'a [u8], EntryV0_1, EntryIndexesV0_1<'a>> {
+    let indexes = EntryIndexesV0_1 {
+        author_xyz_id: MultiIndex::new(
+            |n: &EntryV0_1, k: Vec<u8>| (n.author_xyz_id.clone(), k),
+            "entries",
+            "entries__author_xyz_id",
+        ),
+        coordinates: MultiIndex::new(
+            |n: &EntryV0_1, k: Vec<u8>| (n.coordinates.to_bytes(), k),
+            "entries",
+            "entries__coordinates",
+        ),
+    };
+    IndexedMap::new("entries", indexes)
+}
+
+// Rewrite the config and every entry in the current layout.
+//
+// The single entry fee becomes the only accepted entry fee, priced flat, with no CW20 fees and no
+// cut taken from tips. Text keeps being measured in bytes against the character limit, and no
+// reactions are allowed until the owner configures some. Each xyz keeps its limit of one entry per
+// location, without a cooldown or a minimum stay, and late signatures are disabled.
+//
+// Each legacy entry is removed along with its index entries before the new entry is saved under
+// its length-prefixed primary key as the author xyz's first entry at its location, so every
+// current index is rebuilt from scratch, and entries are counted per location along the way. Each
+// entry is recorded in its author xyz's posting history at its location, so the xyz's next entry
+// there gets the next index.
+// Fields that weren't recorded before 0.2.0 are filled with sentinel values: a zero creation time
+// and block height, and an empty author address. Sequence numbers are assigned in primary key
+// order, since the original creation order is unknown.
+fn migrate_v0_2_0(storage: &mut dyn Storage, _env: &Env) -> StdResult<()> {
+    let legacy_config = CONFIG_V0_1.load(storage)?;
+    CONFIG.save(
+        storage,
+        &Config {
+            xyz_nft_contract: legacy_config.xyz_nft_contract,
+            character_limit: legacy_config.character_limit,
+            limit_mode: LimitMode::Bytes,
+            allowed_reactions: vec![],
+            max_entries_per_location: 1,
+            entry_cooldown_seconds: 0,
+            min_stay_seconds: 0,
+            late_signature_grace_seconds: 0,
+            entry_fees: vec![legacy_config.entry_fee],
+            cw20_entry_fees: vec![],
+            tip_fee_bps: 0,
+            pricing: PricingMode::Flat,
+        },
+    )?;
+
+    let legacy_entries = entries_v0_1()
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut seq = 0;
+    for (legacy_key, legacy_entry) in legacy_entries {
+        entries_v0_1().remove(storage, &legacy_key)?;
+        seq += 1;
+
+        let entry = Entry {
+            author_xyz_id: legacy_entry.author_xyz_id,
+            coordinates: legacy_entry.coordinates,
+            index: 0,
+            text: legacy_entry.text,
+            seq,
+            created_at: Timestamp::from_nanos(0),
+            block_height: 0,
+            author_address: Addr::unchecked(""),
+            edit_count: 0,
+            last_edited: None,
+            tips: vec![],
+            pinned: false,
+            hidden_by_host: false,
+            hidden: false,
+            parent: None,
+            reactions: vec![],
+            score: 0,
+            late: false,
+        };
+        let key = entries_key(entry.author_xyz_id.clone(), entry.coordinates, entry.index);
+        entries().save(storage, key, &entry)?;
+        POSTING_HISTORY.save(
+            storage,
+            (&entry.author_xyz_id, &entry.coordinates.to_bytes()),
+            &PostingHistory {
+                next_index: entry.index + 1,
+                last_signed_at: Some(entry.created_at),
+                signature_count: 1,
+            },
+        )?;
+        LOCATION_ENTRY_COUNTS.update(
+            storage,
+            &entry.coordinates.to_bytes(),
+            |count| -> StdResult<_> { Ok(count.unwrap_or_default() + 1) },
+        )?;
+    }
+    ENTRY_SEQ.save(storage, &seq)?;
+
+    Ok(())
+}
@@ -1,5 +1,5 @@
 // This file defines a specialized MockQuerier for use in testing that allows us to mock
-// the xyz NFT contract's behavior. To use this MockQuerier, use mock_dependencies_from_xyz
+// the xyz NFT contract's behavior, along with this contract's CW20 token balances. To use this MockQuerier, use mock_dependencies_from_xyz
 // instead of cosmwasm_std's mock_dependencies function, setting the desired balance of
 // xyz tokens based on what you're trying to test.
 //
@@ -7,12 +7,14 @@
 
 use std::collections::HashMap;
 
-use collectxyz::nft::{QueryMsg as XyzQueryMsg, XyzTokenInfo};
+use collectxyz::nft::{Config as XyzConfig, QueryMsg as XyzQueryMsg, XyzTokenInfo};
 use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    from_binary, from_slice, to_binary, Coin, ContractResult, OwnedDeps, Querier, QuerierResult,
-    QueryRequest, StdError, SystemError, SystemResult, WasmQuery,
+    coin, from_binary, from_slice, to_binary, Coin, ContractResult, OwnedDeps, Querier,
+    QuerierResult, QueryRequest, StdError, SystemError, SystemResult, Uint128, WasmQuery,
 };
+use cw20::{BalanceResponse, Cw20QueryMsg};
+use cw721::{Approval, ApprovedForAllResponse};
 use terra_cosmwasm::TerraQueryWrapper;
 
 pub fn mock_dependencies_xyz(
@@ -33,6 +35,9 @@ pub fn mock_dependencies_xyz(
 pub struct XyzMockQuerier {
     base: MockQuerier<TerraQueryWrapper>,
     xyz_balances: HashMap<String, XyzTokenInfo>,
+    xyz_operators: HashMap<String, Vec<Approval>>,
+    xyz_config: XyzConfig,
+    cw20_balances: HashMap<String, Uint128>,
 }
 
 impl XyzMockQuerier {
@@ -40,7 +45,24 @@ impl XyzMockQuerier {
         base: MockQuerier<TerraQueryWrapper>,
         xyz_balances: HashMap<String, XyzTokenInfo>,
     ) -> Self {
-        XyzMockQuerier { base, xyz_balances }
+        XyzMockQuerier {
+            base,
+            xyz_balances,
+            xyz_operators: HashMap::new(),
+            // moves take a minute, plus ten seconds per step
+            xyz_config: XyzConfig {
+                public_minting_enabled: true,
+                max_coordinate_value: 1000,
+                token_supply: 1000,
+                wallet_limit: 5,
+                mint_fee: coin(0, "uluna"),
+                base_move_nanos: 60_000_000_000,
+                move_nanos_per_step: 10_000_000_000,
+                base_move_fee: coin(0, "uluna"),
+                move_fee_per_step: Uint128::zero(),
+            },
+            cw20_balances: HashMap::new(),
+        }
     }
 }
 
@@ -49,23 +71,54 @@ impl<'a> XyzMockQuerier {
         self.xyz_balances = xyz_balances;
     }
 
+    // Set the operators that each xyz owner has approved for all of their xyzs, keyed by owner.
+    pub fn update_xyz_operators(&'a mut self, xyz_operators: HashMap<String, Vec<Approval>>) {
+        self.xyz_operators = xyz_operators;
+    }
+
+    // Set this contract's balance of each CW20 token, keyed by token contract address.
+    pub fn update_cw20_balances(&'a mut self, cw20_balances: HashMap<String, Uint128>) {
+        self.cw20_balances = cw20_balances;
+    }
+
     pub fn handle_query(&self, request: &QueryRequest<TerraQueryWrapper>) -> QuerierResult {
         match &request {
             QueryRequest::Wasm(WasmQuery::Smart { contract_addr, msg }) => {
                 if contract_addr.starts_with("xyz-nft-contract") {
-                    if let XyzQueryMsg::XyzNftInfo { token_id } =
-                        from_binary::<XyzQueryMsg>(&msg).unwrap()
-                    {
-                        return self
+                    return match from_binary::<XyzQueryMsg>(msg) {
+                        Ok(XyzQueryMsg::XyzNftInfo { token_id }) => self
                             .xyz_balances
                             .get(&token_id)
                             .map(|xyz| SystemResult::Ok(ContractResult::from(to_binary(xyz))))
                             .unwrap_or(SystemResult::Ok(ContractResult::from(Err(
                                 StdError::not_found("xyz"),
-                            ))));
-                    } else {
-                        panic!("unsupported message type! {}", msg)
-                    }
+                            )))),
+                        Ok(XyzQueryMsg::Config {}) => {
+                            SystemResult::Ok(ContractResult::from(to_binary(&self.xyz_config)))
+                        }
+                        Ok(XyzQueryMsg::ApprovedForAll { owner, .. }) => {
+                            let res = ApprovedForAllResponse {
+                                operators: self
+                                    .xyz_operators
+                                    .get(&owner)
+                                    .cloned()
+                                    .unwrap_or_default(),
+                            };
+                            SystemResult::Ok(ContractResult::from(to_binary(&res)))
+                        }
+                        _ => panic!("unsupported message type! {}", msg),
+                    };
+                }
+                if let Ok(Cw20QueryMsg::Balance { address }) = from_binary::<Cw20QueryMsg>(msg) {
+                    assert_eq!(address, MOCK_CONTRACT_ADDR);
+                    let res = BalanceResponse {
+                        balance: self
+                            .cw20_balances
+                            .get(contract_addr)
+                            .cloned()
+                            .unwrap_or_default(),
+                    };
+                    return SystemResult::Ok(ContractResult::from(to_binary(&res)));
                 }
                 panic!("unsupported query");
             }
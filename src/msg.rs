@@ -2,9 +2,10 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use collectxyz::nft::Coordinates;
-use cosmwasm_std::Coin;
+use cosmwasm_std::{Binary, Coin, Order, Timestamp, Uint128};
+use cw20::{Cw20Coin, Cw20CoinVerified, Cw20ReceiveMsg};
 
-use crate::state::{Config, Entry};
+use crate::state::{Config, ContentPolicy, Entry, EntryRef, LimitMode, Payee, PricingMode, Report};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
@@ -14,31 +15,371 @@ pub struct InstantiateMsg {
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
-    CreateEntry { author_xyz_id: String, text: String },
-    Withdraw { amount: Vec<Coin> },
+    CreateEntry {
+        author_xyz_id: String,
+        text: String,
+    },
+    CreateLateEntry {
+        author_xyz_id: String,
+        text: String,
+    },
+    ReplyToEntry {
+        parent_author_xyz_id: String,
+        parent_coordinates: Coordinates,
+        parent_index: Option<u32>,
+        author_xyz_id: String,
+        text: String,
+    },
+    UpdateEntry {
+        author_xyz_id: String,
+        coordinates: Coordinates,
+        index: Option<u32>,
+        text: String,
+    },
+    DeleteEntry {
+        author_xyz_id: String,
+        coordinates: Coordinates,
+        index: Option<u32>,
+    },
+    TipEntry {
+        author_xyz_id: String,
+        coordinates: Coordinates,
+        index: Option<u32>,
+    },
+    ReactToEntry {
+        author_xyz_id: String,
+        coordinates: Coordinates,
+        index: Option<u32>,
+        reaction: String,
+        reactor_xyz_id: String,
+    },
+    ClaimLocation {
+        xyz_id: String,
+    },
+    SetLocationSurcharge {
+        coordinates: Coordinates,
+        surcharge_bps: u32,
+    },
+    SetEntryPinned {
+        author_xyz_id: String,
+        coordinates: Coordinates,
+        index: Option<u32>,
+        pinned: bool,
+    },
+    SetEntryHiddenByHost {
+        author_xyz_id: String,
+        coordinates: Coordinates,
+        index: Option<u32>,
+        hidden: bool,
+    },
+    UpdateModerators {
+        add: Vec<String>,
+        remove: Vec<String>,
+    },
+    ReportEntry {
+        author_xyz_id: String,
+        coordinates: Coordinates,
+        index: Option<u32>,
+        reporter_xyz_id: String,
+        reason: String,
+    },
+    HideEntry {
+        author_xyz_id: String,
+        coordinates: Coordinates,
+        index: Option<u32>,
+    },
+    RestoreEntry {
+        author_xyz_id: String,
+        coordinates: Coordinates,
+        index: Option<u32>,
+    },
+    Receive(Cw20ReceiveMsg),
+    Withdraw {
+        amount: Vec<Coin>,
+    },
+    WithdrawCw20 {
+        token_contract: String,
+        amount: Uint128,
+    },
+    UpdateConfig(UpdateConfigMsg),
+    UpdateContentPolicy(ContentPolicy),
+    UpdatePayees {
+        payees: Vec<PayeeMsg>,
+    },
+    Distribute {},
+    ProposeNewOwner {
+        new_owner: String,
+    },
+    AcceptOwnership {},
+    CancelOwnershipProposal {},
+    RenounceOwnership {},
+}
+
+// Changes to the config, where each field left unset keeps its current value.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct UpdateConfigMsg {
+    pub xyz_nft_contract: Option<String>,
+    pub character_limit: Option<u32>,
+    pub limit_mode: Option<LimitMode>,
+    pub entry_fees: Option<Vec<Coin>>,
+    pub cw20_entry_fees: Option<Vec<Cw20Coin>>,
+    pub tip_fee_bps: Option<u32>,
+    pub pricing: Option<PricingMode>,
+    pub allowed_reactions: Option<Vec<String>>,
+    pub max_entries_per_location: Option<u32>,
+    pub entry_cooldown_seconds: Option<u64>,
+    pub min_stay_seconds: Option<u64>,
+    pub late_signature_grace_seconds: Option<u64>,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PayeeMsg {
+    pub address: String,
+    pub share_bps: u32,
+}
+
+// Messages embedded in a CW20 Send to this contract, paying the entry fee with the tokens sent.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiveMsg {
+    CreateEntry {
+        author_xyz_id: String,
+        text: String,
+    },
+    CreateLateEntry {
+        author_xyz_id: String,
+        text: String,
+    },
+    ReplyToEntry {
+        parent_author_xyz_id: String,
+        parent_coordinates: Coordinates,
+        parent_index: Option<u32>,
+        author_xyz_id: String,
+        text: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     Entry {
         author_xyz_id: String,
         coordinates: Coordinates,
+        index: Option<u32>,
     },
     EntriesForXyz {
         author_xyz_id: String,
-        start_after: Option<String>,
+        start_after: Option<Binary>,
         limit: Option<u32>,
+        include_hidden: Option<bool>,
     },
     EntriesForCoordinates {
         coordinates: Coordinates,
-        start_after: Option<String>,
+        start_after: Option<Binary>,
+        limit: Option<u32>,
+        include_hidden: Option<bool>,
+    },
+    AllEntries {
+        start_after: Option<Binary>,
+        limit: Option<u32>,
+        order: Option<OrderBy>,
+        include_hidden: Option<bool>,
+    },
+    Replies {
+        parent: EntryRef,
+        start_after: Option<Binary>,
+        limit: Option<u32>,
+        include_hidden: Option<bool>,
+    },
+    TopEntries {
+        coordinates: Option<Coordinates>,
+        start_after: Option<Binary>,
         limit: Option<u32>,
     },
+    EntriesInRegion {
+        min: Coordinates,
+        max: Coordinates,
+        start_after: Option<Binary>,
+        limit: Option<u32>,
+        include_hidden: Option<bool>,
+    },
+    EntriesNear {
+        center: Coordinates,
+        radius: u32,
+        start_after: Option<Binary>,
+        limit: Option<u32>,
+        include_hidden: Option<bool>,
+    },
+    QuoteEntryFee {
+        coordinates: Coordinates,
+    },
+    Location {
+        coordinates: Coordinates,
+    },
+    CanPost {
+        author_xyz_id: String,
+        sender: Option<String>,
+    },
+    SimulateCreateEntry {
+        sender: String,
+        author_xyz_id: String,
+        text: String,
+        funds: Vec<Coin>,
+    },
+    Reports {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    Moderators {},
     Config {},
+    ContentPolicy {},
+    Owner {},
+    PendingDistribution {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderBy {
+    Ascending,
+    Descending,
+}
+
+impl From<OrderBy> for Order {
+    fn from(order: OrderBy) -> Self {
+        match order {
+            OrderBy::Ascending => Order::Ascending,
+            OrderBy::Descending => Order::Descending,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct EntriesResponse {
     pub entries: Vec<Entry>,
+    /// An opaque cursor to pass as `start_after` to fetch the next page of entries, or None if
+    /// this is the last page.
+    pub next_start_after: Option<Binary>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OwnerResponse {
+    pub owner: Option<String>,
+    pub pending_owner: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingPayment {
+    pub payee: Payee,
+    /// The amount this payee would receive if the contract balance were distributed now.
+    pub amount: Vec<Coin>,
+    /// The amount of each accepted CW20 token this payee would receive if distributed now.
+    pub cw20_amount: Vec<Cw20CoinVerified>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingDistributionResponse {
+    pub payments: Vec<PendingPayment>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EntryFeeQuoteResponse {
+    /// The native fees accepted for the next entry at the location; paying any one suffices.
+    pub entry_fees: Vec<Coin>,
+    /// The CW20 token amounts accepted for the next entry at the location.
+    pub cw20_entry_fees: Vec<Cw20CoinVerified>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LocationResponse {
+    pub coordinates: Coordinates,
+    /// The xyz hosting the location's guestbook, if the location has been claimed and its host
+    /// xyz is still there.
+    pub host_xyz_id: Option<String>,
+    pub surcharge_bps: u32,
+    pub entry_count: u32,
+}
+
+// Whether an xyz can create an entry at its current location right now, along with each check
+// that goes into it, so that clients can explain why posting is unavailable.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CanPostResponse {
+    /// Whether every check below passes, so that an entry can be created by paying the fee.
+    pub can_post: bool,
+    /// The current owner of the xyz.
+    pub owner: String,
+    /// Whether the queried sender owns the xyz or is approved to post for it, or None if no
+    /// sender was given.
+    pub sender_approved: Option<bool>,
+    /// The xyz's current location, where its next entry would be created.
+    pub coordinates: Coordinates,
+    /// Whether the xyz has finished relocating to its current location.
+    pub arrived: bool,
+    /// When the xyz will have stayed at its location for the configured minimum stay.
+    pub stay_complete_at: Timestamp,
+    /// Whether the xyz has stayed at its location for the configured minimum stay.
+    pub stay_complete: bool,
+    /// The native fees accepted for the next entry at the location; paying any one suffices.
+    pub entry_fees: Vec<Coin>,
+    /// The CW20 token amounts accepted for the next entry at the location.
+    pub cw20_entry_fees: Vec<Cw20CoinVerified>,
+    /// The number of entries the xyz already has at its location, not counting replies.
+    pub entry_count: u32,
+    /// Whether the xyz already has as many entries at its location as it's allowed.
+    pub entry_cap_reached: bool,
+    /// When the cooldown since the xyz's latest entry at its location ends, not counting replies,
+    /// or None if no cooldown is active.
+    pub cooldown_ends_at: Option<Timestamp>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SimulateCreateEntryResponse {
+    /// Every rule that creating the entry would violate, in the order they're checked. The entry
+    /// would be created if this is empty, and otherwise creating it fails with the error for the
+    /// first violation.
+    pub violations: Vec<Violation>,
+}
+
+// A rule that creating an entry would violate.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Violation {
+    /// The text is longer than the character limit, measured per the limit mode.
+    CharacterLimitExceeded { length: u32, limit: u32 },
+    /// The text breaks the named rule of the content policy, e.g., "banned_substring".
+    ContentPolicyViolation { rule: String },
+    /// The entry being replied to doesn't exist.
+    ParentNotFound {},
+    /// The author xyz couldn't be looked up on the xyz NFT contract.
+    XyzNotFound {},
+    /// The sender doesn't own the author xyz and isn't approved to post for it.
+    Unauthorized {},
+    /// The author xyz hasn't arrived at its current location yet.
+    RelocationInProgress {},
+    /// The author xyz hasn't stayed at its location for the minimum stay yet.
+    MinimumStayNotMet {},
+    /// The author xyz can't leave a late signature at its previous location.
+    LateSignatureUnavailable {},
+    /// The author xyz already has as many entries at the location as it's allowed.
+    EntryCapReached {},
+    /// The cooldown since the author xyz's latest entry at the location is still active.
+    EntryCooldownActive {},
+    /// The funds sent don't cover the entry fee.
+    InsufficientFunds {},
+    /// None of the funds sent are in a denomination accepted for the entry fee.
+    WrongDenom {},
+    /// Any other error that creating the entry would fail with.
+    Other { error: String },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ReportsResponse {
+    pub reports: Vec<Report>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ModeratorsResponse {
+    pub moderators: Vec<String>,
 }
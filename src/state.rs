@@ -1,55 +1,436 @@
+use std::fmt;
+
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use collectxyz::nft::Coordinates;
-use cosmwasm_std::{Addr, Coin};
-use cw_storage_plus::{Index, IndexList, IndexedMap, Item, MultiIndex};
+use cosmwasm_std::{Addr, Coin, Empty, StdResult, Timestamp, Uint128};
+use cw20::Cw20CoinVerified;
+use cw_storage_plus::{
+    Index, IndexList, IndexedMap, Item, Map, MultiIndex, PrimaryKey, U32Key, U64Key, U8Key,
+    UniqueIndex,
+};
+use unicode_segmentation::UnicodeSegmentation;
 
 pub const OWNER: Item<String> = Item::new("owner");
+// The address proposed as the next owner, which must accept before ownership is transferred.
+pub const PENDING_OWNER: Item<String> = Item::new("pending_owner");
+// The addresses allowed to hide and restore guestbook entries, managed by the owner.
+pub const MODERATORS: Map<&Addr, Empty> = Map::new("moderators");
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
     /// The address of the xyz NFT contract.
     pub xyz_nft_contract: Addr,
-    /// The character limit of each guestbook entry, e.g., 240.
+    /// The character limit of each guestbook entry, e.g., 240, counted per the limit mode.
     pub character_limit: u32,
-    /// The fee required to leave an entry in a guestbook.
-    pub entry_fee: Coin,
+    /// How the length of entry text is measured against the character limit.
+    pub limit_mode: LimitMode,
+    /// The fees accepted to leave an entry in a guestbook, e.g., 1000uluna or 1000uusd. Paying
+    /// any one of them is sufficient, and no fee is charged if the list is empty.
+    pub entry_fees: Vec<Coin>,
+    /// The CW20 token amounts accepted to leave an entry in a guestbook, paid by sending tokens
+    /// to this contract with an embedded CreateEntry message. Tokens not listed are rejected.
+    pub cw20_entry_fees: Vec<Cw20CoinVerified>,
+    /// The protocol's cut of tips sent to entry authors, in basis points, e.g., 500 for 5%. The
+    /// cut stays in the contract alongside collected entry fees.
+    pub tip_fee_bps: u32,
+    /// How entry fees scale with the number of entries already at a location.
+    pub pricing: PricingMode,
+    /// The reactions that xyz holders may leave on entries, e.g., "like" or "🔥". Entries can't
+    /// be reacted to if the list is empty.
+    pub allowed_reactions: Vec<String>,
+    /// The most entries that each xyz can have at a single location at once, e.g., 1. Replies
+    /// don't count toward the cap.
+    pub max_entries_per_location: u32,
+    /// The number of seconds an xyz must wait after creating an entry at a location before it
+    /// can create another entry there, or 0 for no cooldown. Replies neither wait for nor start
+    /// the cooldown, and deleting an entry doesn't end it early.
+    pub entry_cooldown_seconds: u64,
+    /// The number of seconds an xyz must have spent at its location since arriving before it can
+    /// create an entry there, or 0 for no minimum stay.
+    pub min_stay_seconds: u64,
+    /// The number of seconds after starting to relocate that an xyz can still sign the guestbook
+    /// at its previous location, as a late signature, or 0 to disable late signatures. The window
+    /// also closes once the xyz arrives at its new location.
+    pub late_signature_grace_seconds: u64,
 }
 
 pub const CONFIG: Item<Config> = Item::new("config");
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PricingMode {
+    /// Every entry costs the configured entry fee.
+    Flat,
+    /// Each entry already at a location adds a fixed fraction of the configured entry fee, in
+    /// basis points, e.g., 1000 adds 10% of the fee per entry.
+    Linear { step_bps: u32 },
+    /// Each entry already at a location multiplies the fee by a fixed growth rate, in basis
+    /// points, e.g., 1000 makes each entry cost 10% more than the last.
+    Exponential { growth_bps: u32 },
+}
+
+impl PricingMode {
+    // Price a new entry at a location that already has `count` entries, given the configured
+    // entry fee. Prices are rounded down, at each step when compounding.
+    pub fn price(&self, fee: Uint128, count: u32) -> StdResult<Uint128> {
+        match self {
+            PricingMode::Flat => Ok(fee),
+            PricingMode::Linear { step_bps } => {
+                let steps = Uint128::from(u128::from(*step_bps) * u128::from(count));
+                Ok(fee.checked_add(fee.checked_mul(steps)?.checked_div(Uint128::new(10000))?)?)
+            }
+            PricingMode::Exponential { growth_bps } => {
+                let growth = Uint128::from(10000 + u128::from(*growth_bps));
+                let mut price = fee;
+                for _ in 0..count {
+                    if price.is_zero() || *growth_bps == 0 {
+                        break;
+                    }
+                    price = price
+                        .checked_mul(growth)?
+                        .checked_div(Uint128::new(10000))?;
+                }
+                Ok(price)
+            }
+        }
+    }
+}
+
+impl fmt::Display for PricingMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PricingMode::Flat => write!(f, "flat"),
+            PricingMode::Linear { step_bps } => write!(f, "linear:{}", step_bps),
+            PricingMode::Exponential { growth_bps } => write!(f, "exponential:{}", growth_bps),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LimitMode {
+    /// Text is measured in UTF-8 bytes, so e.g. an emoji counts for up to 4.
+    Bytes,
+    /// Text is measured in Unicode scalar values.
+    Chars,
+    /// Text is measured in extended grapheme clusters, i.e., characters as readers see them, so
+    /// e.g. a family emoji made of several joined emoji counts for 1.
+    Graphemes,
+}
+
+impl LimitMode {
+    pub fn length(&self, text: &str) -> usize {
+        match self {
+            LimitMode::Bytes => text.len(),
+            LimitMode::Chars => text.chars().count(),
+            LimitMode::Graphemes => text.graphemes(true).count(),
+        }
+    }
+}
+
+impl fmt::Display for LimitMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LimitMode::Bytes => write!(f, "bytes"),
+            LimitMode::Chars => write!(f, "chars"),
+            LimitMode::Graphemes => write!(f, "graphemes"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CharacterCategory {
+    /// Control characters other than line feeds, e.g., tabs, carriage returns and escapes.
+    Control,
+    /// Invisible characters that take up no width, e.g., zero-width spaces and joiners.
+    ZeroWidth,
+    /// Characters that override or embed text direction, which can disguise what text says.
+    Bidi,
+}
+
+impl CharacterCategory {
+    pub fn contains(&self, c: char) -> bool {
+        match self {
+            CharacterCategory::Control => c.is_control() && c != '\n',
+            CharacterCategory::ZeroWidth => {
+                matches!(
+                    c,
+                    '\u{200B}' | '\u{200C}' | '\u{200D}' | '\u{2060}' | '\u{FEFF}'
+                )
+            }
+            CharacterCategory::Bidi => matches!(
+                c,
+                '\u{061C}'
+                    | '\u{200E}'
+                    | '\u{200F}'
+                    | '\u{202A}'..='\u{202E}'
+                    | '\u{2066}'..='\u{2069}'
+            ),
+        }
+    }
+}
+
+impl fmt::Display for CharacterCategory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CharacterCategory::Control => write!(f, "control"),
+            CharacterCategory::ZeroWidth => write!(f, "zero_width"),
+            CharacterCategory::Bidi => write!(f, "bidi"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct ContentPolicy {
+    /// Substrings that entry text may not contain, matched case-insensitively.
+    pub banned_substrings: Vec<String>,
+    /// Categories of characters that entry text may not contain.
+    pub disallowed_characters: Vec<CharacterCategory>,
+    /// The most line breaks that entry text may contain, if limited.
+    pub max_newlines: Option<u32>,
+    /// Whether entry text may not contain links, i.e., anything with a URL scheme or "www.".
+    pub block_urls: bool,
+}
+
+impl ContentPolicy {
+    // Check text against the policy, returning the name of the first rule it breaks, if any.
+    pub fn violation(&self, text: &str) -> Option<String> {
+        let lowercase = text.to_lowercase();
+        if self
+            .banned_substrings
+            .iter()
+            .any(|banned| lowercase.contains(&banned.to_lowercase()))
+        {
+            return Some("banned_substring".to_string());
+        }
+        if let Some(category) = self
+            .disallowed_characters
+            .iter()
+            .find(|category| text.chars().any(|c| category.contains(c)))
+        {
+            return Some(format!("{}_character", category));
+        }
+        if let Some(max_newlines) = self.max_newlines {
+            if text.matches('\n').count() > max_newlines as usize {
+                return Some("max_newlines".to_string());
+            }
+        }
+        if self.block_urls && (lowercase.contains("://") || lowercase.contains("www.")) {
+            return Some("url".to_string());
+        }
+        None
+    }
+}
+
+// The rules that entry text must follow, managed by the owner. No rules apply until it's set.
+pub const CONTENT_POLICY: Item<ContentPolicy> = Item::new("content_policy");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Location {
+    /// The xyz hosting this location's guestbook, whose owner may pin and hide its entries.
+    pub host_xyz_id: String,
+    /// The host's surcharge on entry fees at this location, in basis points, paid to the owner
+    /// of the host xyz.
+    pub surcharge_bps: u32,
+}
+
+// The hosts of claimed locations, keyed by coordinates.
+pub const LOCATIONS: Map<&[u8], Location> = Map::new("locations");
+
+// The number of guestbook entries at each location, not counting replies, keyed by coordinates.
+pub const LOCATION_ENTRY_COUNTS: Map<&[u8], u32> = Map::new("location_entry_counts");
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct PostingHistory {
+    /// The index of the xyz's next entry at the location. Indexes are never reused, even once
+    /// the entries they were assigned to are deleted.
+    pub next_index: u32,
+    /// When the xyz last created an entry at the location that isn't a reply, which its cooldown
+    /// there is measured from.
+    pub last_signed_at: Option<Timestamp>,
+    /// The number of entries the xyz currently has at the location that aren't replies, which
+    /// its cap on entries per location is checked against.
+    pub signature_count: u32,
+}
+
+// Each xyz's posting history at each location it has created entries at, keyed by xyz token ID
+// and coordinates. It's kept apart from the entries themselves, so that deleting an entry neither
+// frees up its index nor cuts the xyz's cooldown short.
+pub const POSTING_HISTORY: Map<(&str, &[u8]), PostingHistory> = Map::new("posting_history");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Payee {
+    /// The address that receives this payee's share of distributed fees.
+    pub address: Addr,
+    /// This payee's share of distributed fees, in basis points, e.g., 2500 for 25%.
+    pub share_bps: u32,
+}
+
+// The recipients that collected fees are split between when they're distributed. The shares of
+// all payees add up to 10000 basis points.
+pub const PAYEES: Item<Vec<Payee>> = Item::new("payees");
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Entry {
     /// The xyz token ID associated with this guestbook entry.
     pub author_xyz_id: String,
     /// The coordinate location associated with this guestbook entry.
     pub coordinates: Coordinates,
+    /// The position of this guestbook entry among the author xyz's entries at its location,
+    /// counting up from 0.
+    pub index: u32,
     /// The text content of the guestbook entry.
     pub text: String,
+    /// The sequence number of the guestbook entry, assigned in order of creation.
+    pub seq: u64,
+    /// The time at which the guestbook entry was created.
+    pub created_at: Timestamp,
+    /// The block height at which the guestbook entry was created.
+    pub block_height: u64,
+    /// The address that created the guestbook entry: the owner of the author xyz at the time, or
+    /// an address the owner approved to post for it.
+    pub author_address: Addr,
+    /// The number of times the guestbook entry has been edited.
+    pub edit_count: u32,
+    /// The time of the most recent edit, if the entry has been edited.
+    pub last_edited: Option<Timestamp>,
+    /// The total tips sent to the author of the guestbook entry, including the protocol's cut.
+    pub tips: Vec<Coin>,
+    /// Whether the location's host has pinned the guestbook entry, which puts it ahead of the
+    /// location's other entries.
+    pub pinned: bool,
+    /// Whether the location's host has hidden the guestbook entry from the location's guestbook.
+    pub hidden_by_host: bool,
+    /// Whether a moderator has hidden the guestbook entry.
+    pub hidden: bool,
+    /// The guestbook entry that this entry replies to, if it's a reply. The parent entry may
+    /// since have been deleted.
+    pub parent: Option<EntryRef>,
+    /// The number of xyzs that left each reaction on the guestbook entry.
+    pub reactions: Vec<ReactionCount>,
+    /// The total number of reactions left on the guestbook entry, which entries are ranked by.
+    pub score: u32,
+    /// Whether the guestbook entry is a late signature, left at the author xyz's previous
+    /// location after it started relocating.
+    pub late: bool,
 }
 
-// Build a composite primary key from an xyz token ID and a set of coordinates.
-pub fn entries_key(author_xyz_id: String, coordinates: Coordinates) -> Vec<u8> {
-    vec![author_xyz_id.as_bytes(), &coordinates.to_bytes()].concat()
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ReactionCount {
+    pub reaction: String,
+    pub count: u32,
+}
+
+// A reference to a guestbook entry by its primary key fields.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EntryRef {
+    pub author_xyz_id: String,
+    pub coordinates: Coordinates,
+    pub index: u32,
+}
+
+impl EntryRef {
+    pub fn key(&self) -> EntryKey {
+        entries_key(self.author_xyz_id.clone(), self.coordinates, self.index)
+    }
+}
+
+// The sequence number of the most recently created guestbook entry.
+pub const ENTRY_SEQ: Item<u64> = Item::new("entry_seq");
+
+// The reactions each xyz has left on each guestbook entry, keyed by the entry's sequence number,
+// the reacting xyz token ID and the reaction, so that a deleted entry's reactions never carry
+// over to a new entry with the same primary key.
+pub const REACTIONS: Map<(U64Key, &str, &str), Empty> = Map::new("reactions");
+
+// The composite primary key of a guestbook entry: an xyz token ID, a set of coordinates, and the
+// entry's index among the xyz's entries at those coordinates.
+pub type EntryKey = (String, Vec<u8>, U32Key);
+
+// Build a composite primary key from an xyz token ID, a set of coordinates and an entry index.
+// The token ID and coordinates are length-prefixed when the key is joined, so distinct keys never
+// collide, and an xyz's entries at a location share a prefix ordered by index.
+pub fn entries_key(author_xyz_id: String, coordinates: Coordinates, index: u32) -> EntryKey {
+    (author_xyz_id, coordinates.to_bytes(), U32Key::new(index))
+}
+
+// The side length of the cubic region buckets that guestbook entries are grouped into, so that
+// entries within a region can be found without scanning every entry.
+pub const REGION_BUCKET_SIZE: i64 = 16;
+
+// A point in space, identified by its position along each axis.
+pub type Point = (i64, i64, i64);
+
+// A region bucket, identified by its position along each axis.
+pub type RegionBucket = (i64, i64, i64);
+
+pub fn coordinates_to_point(coordinates: &Coordinates) -> Point {
+    (coordinates.x, coordinates.y, coordinates.z)
+}
+
+// Find the region bucket containing a point.
+pub fn region_bucket(point: Point) -> RegionBucket {
+    (
+        point.0.div_euclid(REGION_BUCKET_SIZE),
+        point.1.div_euclid(REGION_BUCKET_SIZE),
+        point.2.div_euclid(REGION_BUCKET_SIZE),
+    )
+}
+
+pub fn region_bucket_to_bytes(bucket: RegionBucket) -> Vec<u8> {
+    [
+        bucket.0.to_be_bytes(),
+        bucket.1.to_be_bytes(),
+        bucket.2.to_be_bytes(),
+    ]
+    .concat()
+}
+
+// The rank of an entry among the entries at its location, where entries pinned by the location's
+// host come first.
+pub fn pin_rank(entry: &Entry) -> U8Key {
+    U8Key::new(if entry.pinned { 0 } else { 1 })
+}
+
+// The rank of an entry in its location's ranking by score, highest first, where entries pinned by
+// the location's host rank above every entry that isn't, and each group is ranked by score.
+pub fn location_score_rank(entry: &Entry) -> U64Key {
+    U64Key::new(u64::from(entry.pinned) << 32 | u64::from(entry.score))
 }
 
 // Define storage multiindexes to make it easier to load all guestbook entries associated with
-// a given xyz ID or a given set of coordinates.
+// a given xyz ID, a given set of coordinates, or a given region bucket, and a unique index to
+// load all guestbook entries in the order they were created.
 pub struct EntryIndexes<'a> {
     pub author_xyz_id: MultiIndex<'a, (String, Vec<u8>), Entry>,
-    pub coordinates: MultiIndex<'a, (Vec<u8>, Vec<u8>), Entry>,
+    pub coordinates: MultiIndex<'a, (Vec<u8>, U8Key, Vec<u8>), Entry>,
+    pub region: MultiIndex<'a, (Vec<u8>, Vec<u8>), Entry>,
+    pub seq: UniqueIndex<'a, U64Key, Entry>,
+    pub replies: MultiIndex<'a, (Vec<u8>, Vec<u8>), Entry>,
+    pub score: MultiIndex<'a, (U32Key, Vec<u8>), Entry>,
+    pub location_score: MultiIndex<'a, (Vec<u8>, U64Key, Vec<u8>), Entry>,
 }
 
 impl<'a> IndexList<Entry> for EntryIndexes<'a> {
     fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Entry>> + '_> {
-        let v: Vec<&dyn Index<Entry>> = vec![&self.author_xyz_id, &self.coordinates];
+        let v: Vec<&dyn Index<Entry>> = vec![
+            &self.author_xyz_id,
+            &self.coordinates,
+            &self.region,
+            &self.seq,
+            &self.replies,
+            &self.score,
+            &self.location_score,
+        ];
         Box::new(v.into_iter())
     }
 }
 
 // Build and return the indexed map of guestbook entries for use in contract handlers.
-pub fn entries<'a>() -> IndexedMap<'a, &'a [u8], Entry, EntryIndexes<'a>> {
+pub fn entries<'a>() -> IndexedMap<'a, EntryKey, Entry, EntryIndexes<'a>> {
     let indexes = EntryIndexes {
         author_xyz_id: MultiIndex::new(
             |n: &Entry, k: Vec<u8>| (n.author_xyz_id.clone(), k),
@@ -57,10 +438,90 @@ pub fn entries<'a>() -> IndexedMap<'a, &'a [u8], Entry, EntryIndexes<'a>> {
             "entries__author_xyz_id",
         ),
         coordinates: MultiIndex::new(
-            |n: &Entry, k: Vec<u8>| (n.coordinates.to_bytes(), k),
+            |n: &Entry, k: Vec<u8>| (n.coordinates.to_bytes(), pin_rank(n), k),
             "entries",
             "entries__coordinates",
         ),
+        region: MultiIndex::new(
+            |n: &Entry, k: Vec<u8>| {
+                let bucket = region_bucket(coordinates_to_point(&n.coordinates));
+                (region_bucket_to_bytes(bucket), k)
+            },
+            "entries",
+            "entries__region",
+        ),
+        seq: UniqueIndex::new(|n: &Entry| U64Key::new(n.seq), "entries__seq"),
+        // Replies are indexed by their parent's joined primary key. Entries that aren't replies
+        // all share the empty key, which no parent's key can be.
+        replies: MultiIndex::new(
+            |n: &Entry, k: Vec<u8>| {
+                let parent = n.parent.as_ref().map(|parent| parent.key().joined_key());
+                (parent.unwrap_or_default(), k)
+            },
+            "entries",
+            "entries__replies",
+        ),
+        score: MultiIndex::new(
+            |n: &Entry, k: Vec<u8>| (U32Key::new(n.score), k),
+            "entries",
+            "entries__score",
+        ),
+        location_score: MultiIndex::new(
+            |n: &Entry, k: Vec<u8>| (n.coordinates.to_bytes(), location_score_rank(n), k),
+            "entries",
+            "entries__location_score",
+        ),
     };
     IndexedMap::new("entries", indexes)
 }
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Report {
+    /// The ID of the report, assigned in order of submission.
+    pub id: u64,
+    /// The xyz token ID of the reported guestbook entry.
+    pub author_xyz_id: String,
+    /// The coordinate location of the reported guestbook entry.
+    pub coordinates: Coordinates,
+    /// The index of the reported guestbook entry among its author's entries at its location.
+    pub index: u32,
+    /// The xyz token ID of the reporter.
+    pub reporter_xyz_id: String,
+    /// The address that owned the reporter xyz when the report was made.
+    pub reporter_address: Addr,
+    /// The reporter's reason for reporting the guestbook entry.
+    pub reason: String,
+    /// The time at which the report was made.
+    pub created_at: Timestamp,
+}
+
+// The ID of the most recently submitted report.
+pub const REPORT_SEQ: Item<u64> = Item::new("report_seq");
+
+// Define a storage multiindex to make it easier to find the open reports on a given guestbook
+// entry, keyed by the entry's joined primary key.
+pub struct ReportIndexes<'a> {
+    pub entry: MultiIndex<'a, (Vec<u8>, Vec<u8>), Report>,
+}
+
+impl<'a> IndexList<Report> for ReportIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Report>> + '_> {
+        let v: Vec<&dyn Index<Report>> = vec![&self.entry];
+        Box::new(v.into_iter())
+    }
+}
+
+// Build and return the indexed map of open reports, i.e., the moderation queue.
+pub fn reports<'a>() -> IndexedMap<'a, U64Key, Report, ReportIndexes<'a>> {
+    let indexes = ReportIndexes {
+        entry: MultiIndex::new(
+            |r: &Report, k: Vec<u8>| {
+                let entry_key = entries_key(r.author_xyz_id.clone(), r.coordinates, r.index);
+                (entry_key.joined_key(), k)
+            },
+            "reports",
+            "reports__entry",
+        ),
+    };
+    IndexedMap::new("reports", indexes)
+}